@@ -1,5 +1,6 @@
 pub use arbitrary;
 use once_cell::sync::OnceCell;
+use std::borrow::Cow;
 
 #[doc(hidden)]
 pub static RUST_LIBFUZZER_DEBUG_PATH: OnceCell<String> = OnceCell::new();
@@ -16,6 +17,65 @@ pub fn rust_fuzzer_initialize() {
     }
 }
 
+/// Whether the input that was just run should be kept in the corpus.
+///
+/// Fuzz targets may return this from their body to reject inputs they
+/// consider uninteresting, returning `()` is the same as [`Corpus::Keep`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corpus {
+    /// Keep the input in the corpus if it is interesting
+    Keep,
+    /// Never add the input to the corpus
+    Reject,
+}
+
+impl From<()> for Corpus {
+    fn from(_: ()) -> Self {
+        Corpus::Keep
+    }
+}
+
+impl Corpus {
+    /// The code returned to the runtime, following libFuzzer's convention
+    #[doc(hidden)]
+    pub fn to_libfuzzer_code(self) -> i32 {
+        match self {
+            Corpus::Keep => 0,
+            Corpus::Reject => -1,
+        }
+    }
+}
+
+/// The index of the entry of [`fuzz_targets!`] picked with `--entry`, which
+/// the runtime passes on in `CARGO_LIBAFL_ENTRY`, or of the first one
+#[doc(hidden)]
+pub fn selected_entry(names: &[&str]) -> usize {
+    static SELECTED: OnceCell<usize> = OnceCell::new();
+    *SELECTED.get_or_init(|| {
+        let name = match std::env::var("CARGO_LIBAFL_ENTRY") {
+            Ok(name) => name,
+            Err(_) => return 0,
+        };
+        names
+            .iter()
+            .position(|&entry| entry == name)
+            .unwrap_or_else(|| {
+                eprintln!(
+                    "Unknown entry {:?}, this target has: {}",
+                    name,
+                    names.join(", ")
+                );
+                std::process::exit(1);
+            })
+    })
+}
+
+/// The decoder of targets without `decode`, passing the input through as is
+#[doc(hidden)]
+pub fn decode_identity(bytes: &[u8]) -> Cow<'_, [u8]> {
+    Cow::Borrowed(bytes)
+}
+
 /// Define a fuzz target.
 ///
 /// ## Example
@@ -26,7 +86,7 @@ pub fn rust_fuzzer_initialize() {
 /// ```no_run
 /// #![no_main]
 ///
-/// use libfuzzer_sys::fuzz_target;
+/// use cargo_libafl_helper::fuzz_target;
 ///
 /// // Note: `|input|` is short for `|input: &[u8]|`.
 /// fuzz_target!(|input| {
@@ -40,7 +100,7 @@ pub fn rust_fuzzer_initialize() {
 /// The input is a `&[u8]` slice by default, but you can take arbitrary input
 /// types, as long as the type implements [the `arbitrary` crate's `Arbitrary`
 /// trait](https://docs.rs/arbitrary/*/arbitrary/trait.Arbitrary.html) (which is
-/// also re-exported as `libfuzzer_sys::arbitrary::Arbitrary` for convenience).
+/// also re-exported as `cargo_libafl_helper::arbitrary::Arbitrary` for convenience).
 ///
 /// For example, if you wanted to take an arbitrary RGB color, you could do the
 /// following:
@@ -49,7 +109,7 @@ pub fn rust_fuzzer_initialize() {
 /// #![no_main]
 /// # mod foo {
 ///
-/// use libfuzzer_sys::{arbitrary::{Arbitrary, Error, Unstructured}, fuzz_target};
+/// use cargo_libafl_helper::{arbitrary::{Arbitrary, Error, Unstructured}, fuzz_target};
 ///
 /// #[derive(Debug)]
 /// pub struct Rgb {
@@ -82,12 +142,201 @@ pub fn rust_fuzzer_initialize() {
 ///
 /// You can also enable the `arbitrary` crate's custom derive via this crate's
 /// `"arbitrary-derive"` cargo feature.
+///
+/// ## Rejecting Inputs
+///
+/// The body may return a [`Corpus`] to keep inputs out of the corpus, for
+/// example the ones that do not decode into anything meaningful:
+///
+/// ```no_run
+/// #![no_main]
+///
+/// use cargo_libafl_helper::{fuzz_target, Corpus};
+///
+/// fuzz_target!(|data: &[u8]| {
+///     match std::str::from_utf8(data) {
+///         Ok(_text) => Corpus::Keep,
+///         Err(_) => Corpus::Reject,
+///     }
+/// });
+/// ```
+///
+/// ## Initialization
+///
+/// One-time setup, like initializing a logger or loading a file the target
+/// needs, can be passed in an `init:` block. It runs exactly once, before the
+/// first input is handed to the target.
+///
+/// ```no_run
+/// #![no_main]
+///
+/// use cargo_libafl_helper::fuzz_target;
+///
+/// fuzz_target!(init: {
+///     std::env::set_var("MY_CRATE_LOG", "off");
+/// }, |data: &[u8]| {
+///     let _ = data;
+/// });
+/// ```
+///
+/// ## Decoding Inputs
+///
+/// Targets that expect an encoded input, like hex or base64, can pass a
+/// `decode` function of type `fn(&[u8]) -> Cow<[u8]>`. The fuzzer mutates and
+/// stores the raw bytes, and the body gets the decoded ones. It goes after
+/// `init:`, if any.
+///
+/// ```no_run
+/// #![no_main]
+///
+/// use cargo_libafl_helper::fuzz_target;
+/// use std::borrow::Cow;
+///
+/// fn unhex(raw: &[u8]) -> Cow<[u8]> {
+///     let digit = |c: u8| (c as char).to_digit(16).unwrap_or(0) as u8;
+///     Cow::Owned(raw.chunks(2).map(|pair| pair.iter().fold(0, |acc, &c| acc << 4 | digit(c))).collect())
+/// }
+///
+/// fuzz_target!(decode = unhex, |data: &[u8]| {
+///     let _ = data;
+/// });
+/// ```
 #[macro_export]
 macro_rules! fuzz_target {
+    (init: $init:expr, decode = $decode:expr, |$bytes:ident| $body:block) => {
+        /// Auto-generated function
+        #[no_mangle]
+        pub extern "C" fn rust_fuzzer_test_input(raw: &[u8]) -> i32 {
+            $crate::__fuzz_entry!(entry, init: $init, decode = $decode, |$bytes| $body);
+            entry(raw)
+        }
+    };
+
+    (init: $init:expr, decode = $decode:expr, |$data:ident: &[u8]| $body:block) => {
+        $crate::fuzz_target!(init: $init, decode = $decode, |$data| $body);
+    };
+
+    (init: $init:expr, decode = $decode:expr, |$data:ident: $dty: ty| $body:block) => {
+        /// Auto-generated function
+        #[no_mangle]
+        pub extern "C" fn rust_fuzzer_test_input(raw: &[u8]) -> i32 {
+            $crate::__fuzz_entry!(entry, init: $init, decode = $decode, |$data: $dty| $body);
+            entry(raw)
+        }
+    };
+
+    (init: $init:expr, |$bytes:ident| $body:block) => {
+        $crate::fuzz_target!(init: $init, decode = $crate::decode_identity, |$bytes| $body);
+    };
+
+    (init: $init:expr, |$data:ident: &[u8]| $body:block) => {
+        $crate::fuzz_target!(init: $init, decode = $crate::decode_identity, |$data| $body);
+    };
+
+    (init: $init:expr, |$data:ident: $dty: ty| $body:block) => {
+        $crate::fuzz_target!(init: $init, decode = $crate::decode_identity, |$data: $dty| $body);
+    };
+
+    (decode = $decode:expr, |$bytes:ident| $body:block) => {
+        $crate::fuzz_target!(init: (), decode = $decode, |$bytes| $body);
+    };
+
+    (decode = $decode:expr, |$data:ident: &[u8]| $body:block) => {
+        $crate::fuzz_target!(init: (), decode = $decode, |$data| $body);
+    };
+
+    (decode = $decode:expr, |$data:ident: $dty: ty| $body:block) => {
+        $crate::fuzz_target!(init: (), decode = $decode, |$data: $dty| $body);
+    };
+
     (|$bytes:ident| $body:block) => {
+        $crate::fuzz_target!(init: (), |$bytes| $body);
+    };
+
+    (|$data:ident: &[u8]| $body:block) => {
+        $crate::fuzz_target!(init: (), |$data| $body);
+    };
+
+    (|$data:ident: $dty: ty| $body:block) => {
+        $crate::fuzz_target!(init: (), |$data: $dty| $body);
+    };
+}
+
+/// Define several fuzz targets in one binary, to pick with `--entry <NAME>`.
+///
+/// Each entry takes its input like the target of [`fuzz_target!`], as a
+/// `&[u8]` slice or an [`Arbitrary`](arbitrary::Arbitrary) type. The first
+/// entry runs when no `--entry` is given. Suites of targets that share most of
+/// their code then only build a single binary.
+///
+/// ```no_run
+/// #![no_main]
+///
+/// use cargo_libafl_helper::fuzz_targets;
+///
+/// fuzz_targets! {
+///     strict = |data: &[u8]| {
+///         let _ = my_crate::parse(data, true);
+///     },
+///     lenient = |data: &[u8]| {
+///         let _ = my_crate::parse(data, false);
+///     },
+/// }
+/// # mod my_crate { pub fn parse(_: &[u8], _: bool) -> Result<(), ()> { unimplemented!() } }
+/// ```
+///
+/// Then run the second entry with `cargo libafl run <target> -- --entry lenient`.
+#[macro_export]
+macro_rules! fuzz_targets {
+    ($($entries:tt)*) => {
+        $crate::__fuzz_targets!(@entries [] $($entries)*);
+    };
+}
+
+/// Collects the entries of [`fuzz_targets!`], one at a time
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fuzz_targets {
+    (@entries [$($done:tt)*] $name:ident = |$data:ident: &[u8]| $body:block $(, $($rest:tt)*)?) => {
+        $crate::__fuzz_targets!(@entries [$($done)* ($name, |$data| $body)] $($($rest)*)?);
+    };
+
+    (@entries [$($done:tt)*] $name:ident = |$data:ident: $dty:ty| $body:block $(, $($rest:tt)*)?) => {
+        $crate::__fuzz_targets!(@entries [$($done)* ($name, |$data: $dty| $body)] $($($rest)*)?);
+    };
+
+    (@entries [$($done:tt)*] $name:ident = |$bytes:ident| $body:block $(, $($rest:tt)*)?) => {
+        $crate::__fuzz_targets!(@entries [$($done)* ($name, |$bytes| $body)] $($($rest)*)?);
+    };
+
+    (@entries [$(($name:ident, $($entry:tt)*))+]) => {
         /// Auto-generated function
         #[no_mangle]
-        pub extern "C" fn rust_fuzzer_test_input($bytes: &[u8]) {
+        pub extern "C" fn rust_fuzzer_test_input(raw: &[u8]) -> i32 {
+            $(
+                $crate::__fuzz_entry!($name, init: (), decode = $crate::decode_identity, $($entry)*);
+            )+
+            let entries: &[fn(&[u8]) -> i32] = &[$($name),+];
+            entries[$crate::selected_entry(&[$(stringify!($name)),+])](raw)
+        }
+    };
+}
+
+/// Defines the function `$name` that runs the body of a target on a raw input
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fuzz_entry {
+    ($name:ident, init: $init:expr, decode = $decode:expr, |$bytes:ident| $body:block) => {
+        fn $name(raw: &[u8]) -> i32 {
+            // Run the user-provided initialization before the first input
+            static INIT: std::sync::Once = std::sync::Once::new();
+            INIT.call_once(|| {
+                $init;
+            });
+
+            let decoded: std::borrow::Cow<[u8]> = ($decode)(raw);
+            let $bytes: &[u8] = &decoded;
+
             // When `RUST_LIBFUZZER_DEBUG_PATH` is set, write the debug
             // formatting of the input to that file. This is only intended for
             // `cargo fuzz`'s use!
@@ -99,23 +348,31 @@ macro_rules! fuzz_target {
                     .expect("failed to create `RUST_LIBFUZZER_DEBUG_PATH` file");
                 writeln!(&mut file, "{:?}", $bytes)
                     .expect("failed to write to `RUST_LIBFUZZER_DEBUG_PATH` file");
-                return;
+                return 0;
             }
 
-            $body
+            let corpus: $crate::Corpus = (|| $body)().into();
+            corpus.to_libfuzzer_code()
         }
     };
 
-    (|$data:ident: &[u8]| $body:block) => {
-        $crate::fuzz_target!(|$data| $body);
+    ($name:ident, init: $init:expr, decode = $decode:expr, |$data:ident: &[u8]| $body:block) => {
+        $crate::__fuzz_entry!($name, init: $init, decode = $decode, |$data| $body);
     };
 
-    (|$data:ident: $dty: ty| $body:block) => {
-        /// Auto-generated function
-        #[no_mangle]
-        pub extern "C" fn rust_fuzzer_test_input(bytes: &[u8]) {
+    ($name:ident, init: $init:expr, decode = $decode:expr, |$data:ident: $dty: ty| $body:block) => {
+        fn $name(raw: &[u8]) -> i32 {
             use $crate::arbitrary::{Arbitrary, Unstructured};
 
+            // Run the user-provided initialization before the first input
+            static INIT: std::sync::Once = std::sync::Once::new();
+            INIT.call_once(|| {
+                $init;
+            });
+
+            let decoded: std::borrow::Cow<[u8]> = ($decode)(raw);
+            let bytes: &[u8] = &decoded;
+
             // Early exit if we don't have enough bytes for the `Arbitrary`
             // implementation. This helps the fuzzer avoid exploring all the
             // different not-enough-input-bytes paths inside the `Arbitrary`
@@ -123,7 +380,7 @@ macro_rules! fuzz_target {
             // get to longer inputs that actually lead to interesting executions
             // quicker.
             if bytes.len() < <$dty as Arbitrary>::size_hint(0).0 {
-                return;
+                return $crate::Corpus::Keep.to_libfuzzer_code();
             }
 
             let mut u = Unstructured::new(bytes);
@@ -143,15 +400,16 @@ macro_rules! fuzz_target {
                     Err(err) => writeln!(&mut file, "Arbitrary Error: {}", err),
                 })
                 .expect("failed to write to `RUST_LIBFUZZER_DEBUG_PATH` file");
-                return;
+                return 0;
             }
 
             let $data = match data {
                 Ok(d) => d,
-                Err(_) => return,
+                Err(_) => return $crate::Corpus::Keep.to_libfuzzer_code(),
             };
 
-            $body
+            let corpus: $crate::Corpus = (|| $body)().into();
+            corpus.to_libfuzzer_code()
         }
     };
 }
@@ -2,7 +2,6 @@ use std::{
     env, fs,
     io::{Read, Write},
     path::Path,
-    process::Command,
 };
 
 #[path = "src/common.rs"]
@@ -11,12 +10,11 @@ mod common;
 fn main() {
     println!("cargo:rustc-env=TARGET={}", env::var("TARGET").unwrap());
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed=cargo-libafl-runtime/runtime.rs");
-    println!("cargo:rerun-if-changed=cargo-libafl-runtime/Cargo.toml");
-
-    if env::var("PUBLISH_ON_CRATES").is_ok() || env::var("DOCS_RS").is_ok() {
-        return;
+    for source in common::RUNTIME_SOURCES {
+        println!("cargo:rerun-if-changed=cargo-libafl-runtime/{}", source);
     }
+    println!("cargo:rerun-if-changed=cargo-libafl-runtime/Cargo.toml");
+    println!("cargo:rerun-if-env-changed=CARGO_LIBAFL_RUNTIME_TARGET");
 
     let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
     let manifest_dir = manifest_dir.to_string_lossy().to_string();
@@ -27,7 +25,7 @@ fn main() {
     let out_path = Path::new(&out_dir);
 
     let mut file =
-        fs::File::open(&rt_path.join("Cargo.toml")).expect("Couldn't open template.Cargo.toml");
+        fs::File::open(rt_path.join("Cargo.toml")).expect("Couldn't open template.Cargo.toml");
     let mut template = String::new();
     file.read_to_string(&mut template)
         .expect("Couldn't read template.Cargo.toml");
@@ -38,29 +36,35 @@ fn main() {
         &format!("version = \"{}\"", env!("CARGO_PKG_VERSION")),
     );
 
-    let mut file =
-        fs::File::create(&out_path.join("Cargo.toml")).expect("Couldn't open Cargo.toml");
+    let mut file = fs::File::create(out_path.join("Cargo.toml")).expect("Couldn't open Cargo.toml");
     file.write_all(template.as_bytes())
         .expect("Couldn't write Cargo.toml");
     drop(file);
 
-    fs::copy(rt_path.join("runtime.rs"), out_path.join("runtime.rs"))
-        .expect("Couldn't copy runtime.rs");
+    for source in common::RUNTIME_SOURCES {
+        fs::copy(rt_path.join(source), out_path.join(source))
+            .unwrap_or_else(|_| panic!("Couldn't copy {}", source));
+    }
+
+    // The runtime sources are kept for `cargo libafl` to build the runtime for other targets
+    if env::var("PUBLISH_ON_CRATES").is_ok() || env::var("DOCS_RS").is_ok() {
+        return;
+    }
 
-    assert!(Command::new("cargo")
-        .current_dir(&out_path)
-        .env("CARGO_TARGET_DIR", out_path.join("rt"))
-        .arg("build")
-        .arg(&format!("--manifest-path={}/Cargo.toml", out_dir))
-        .arg("--release")
-        .status()
-        .unwrap()
-        .success());
+    // Defaults to the target cargo-libafl is built for, others are built on first use
+    let triple =
+        env::var("CARGO_LIBAFL_RUNTIME_TARGET").unwrap_or_else(|_| env::var("TARGET").unwrap());
+    let rt_target_dir = out_path.join("rt");
+    assert!(
+        common::runtime_build_command(out_path, &rt_target_dir, &triple, &[])
+            .status()
+            .unwrap()
+            .success()
+    );
 
-    let archive = out_path
-        .join("rt")
-        .join("release")
-        .join("libcargo_libafl_runtime.a");
-    fs::copy(archive, common::archive_file_path())
-        .expect("Couldn't copy libcargo_libafl_runtime.a");
+    fs::copy(
+        common::built_archive(&rt_target_dir, &triple),
+        common::archive_file_path(&triple, &[]),
+    )
+    .expect("Couldn't copy libcargo_libafl_runtime.a");
 }
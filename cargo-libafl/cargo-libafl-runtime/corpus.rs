@@ -0,0 +1,520 @@
+//! The corpora of the fuzzer: the one picked on the commandline for the testcases, and the
+//! one that stores the solutions like libFuzzer does
+
+use clap::ValueEnum;
+use core::cell::RefCell;
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use libafl::{
+    bolts::AsSlice,
+    corpus::{
+        ondisk::OnDiskMetadataFormat, CachedOnDiskCorpus, Corpus, InMemoryCorpus, OnDiskCorpus,
+        Testcase,
+    },
+    inputs::{HasTargetBytes, Input},
+    prelude::GeneralizedInput,
+    state::HasMetadata,
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{CrashMetadata, ProvenanceMetadata, SolutionKind};
+
+/// The on-disk formats of the corpus that can be picked on the commandline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CorpusFormat {
+    /// The plain bytes of the inputs, that other fuzzers can read
+    Raw,
+    /// The serialized inputs, including their generalization for grimoire
+    Generalized,
+}
+
+/// Reads a seed file, and decompresses it if it is a `.gz` entry of a corpus written with
+/// `--compress-corpus`
+pub(crate) fn read_seed(path: &Path, compressed: bool) -> std::io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if !compressed || path.extension().map_or(true, |ext| ext != "gz") {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// The name of a solution, like libFuzzer's `crash-<sha1>` after the bytes of its input
+fn solution_name(prefix: &str, bytes: &[u8]) -> String {
+    format!("{}-{}", prefix, sha1_smol::Sha1::from(bytes).digest())
+}
+
+/// Inputs that can be rebuilt from the plain bytes [`RawOnDiskCorpus`] writes to disk
+pub(crate) trait FromRawBytes {
+    fn from_raw_bytes(bytes: Vec<u8>) -> Self;
+}
+
+impl FromRawBytes for GeneralizedInput {
+    fn from_raw_bytes(bytes: Vec<u8>) -> Self {
+        GeneralizedInput::new(bytes)
+    }
+}
+
+/// Writes the plain bytes of the testcases to disk, unlike [`CachedOnDiskCorpus`] that writes
+/// the serialized inputs. Like it, only the inputs of the `cache_len` entries loaded last are
+/// kept in memory, the others are read from their files again when they are fuzzed.
+/// With `compress`, the bytes are gzipped into `<name>.gz` files, see [`read_seed`].
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "I: serde::de::DeserializeOwned")]
+pub(crate) struct RawOnDiskCorpus<I>
+where
+    I: Input,
+{
+    entries: InMemoryCorpus<I>,
+    /// The entries whose input is in memory, the one loaded first at the front
+    cached: RefCell<VecDeque<usize>>,
+    cache_len: usize,
+    dir: PathBuf,
+    compress: bool,
+}
+
+impl<I> RawOnDiskCorpus<I>
+where
+    I: Input + HasTargetBytes + FromRawBytes,
+{
+    pub(crate) fn new(dir: PathBuf, cache_len: usize, compress: bool) -> Result<Self, Error> {
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            entries: InMemoryCorpus::new(),
+            cached: RefCell::new(VecDeque::new()),
+            cache_len,
+            dir,
+            compress,
+        })
+    }
+
+    /// Writes the bytes of the input of `testcase` to the corpus dir
+    fn save(&self, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        let input = testcase
+            .input()
+            .as_ref()
+            .ok_or_else(|| Error::illegal_argument("The testcase has no input to save"))?;
+        let filename = match testcase.filename() {
+            Some(filename) => filename.clone(),
+            None => {
+                let mut name = input.generate_name(self.entries.count());
+                if self.compress {
+                    name.push_str(".gz");
+                }
+                self.dir.join(name).to_string_lossy().to_string()
+            }
+        };
+        if self.compress {
+            let mut encoder = flate2::write::GzEncoder::new(
+                fs::File::create(&filename)?,
+                flate2::Compression::default(),
+            );
+            encoder.write_all(input.target_bytes().as_slice())?;
+            encoder.finish()?;
+        } else {
+            fs::write(&filename, input.target_bytes().as_slice())?;
+        }
+        *testcase.filename_mut() = Some(filename);
+        Ok(())
+    }
+
+    /// Marks the input of the entry at `idx` as loaded last, and drops the inputs of the
+    /// entries loaded first beyond `cache_len`, except for the one at `idx` and borrowed ones
+    fn cache(&self, idx: usize) -> Result<(), Error> {
+        let mut cached = self.cached.borrow_mut();
+        cached.retain(|&cached_idx| cached_idx != idx);
+        cached.push_back(idx);
+        let mut skipped = 0;
+        while cached.len() > self.cache_len && skipped < cached.len() {
+            let oldest = cached.pop_front().unwrap();
+            match self.entries.get(oldest)?.try_borrow_mut() {
+                Ok(mut testcase) if oldest != idx => *testcase.input_mut() = None,
+                _ => {
+                    cached.push_back(oldest);
+                    skipped += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<I> Corpus<I> for RawOnDiskCorpus<I>
+where
+    I: Input + HasTargetBytes + FromRawBytes,
+{
+    fn count(&self) -> usize {
+        self.entries.count()
+    }
+
+    fn add(&mut self, mut testcase: Testcase<I>) -> Result<usize, Error> {
+        self.save(&mut testcase)?;
+        let idx = self.entries.add(testcase)?;
+        self.cache(idx)?;
+        Ok(idx)
+    }
+
+    fn replace(&mut self, idx: usize, mut testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        self.save(&mut testcase)?;
+        let old = self.entries.replace(idx, testcase)?;
+        if old.filename() != self.entries.get(idx)?.borrow().filename() {
+            if let Some(filename) = old.filename() {
+                fs::remove_file(filename)?;
+            }
+        }
+        self.cache(idx)?;
+        Ok(old)
+    }
+
+    fn remove(&mut self, idx: usize) -> Result<Option<Testcase<I>>, Error> {
+        let testcase = self.entries.remove(idx)?;
+        if let Some(filename) = testcase
+            .as_ref()
+            .and_then(|testcase| testcase.filename().as_ref())
+        {
+            fs::remove_file(filename)?;
+        }
+        // The entries after it moved down by one
+        let mut cached = self.cached.borrow_mut();
+        cached.retain(|&cached_idx| cached_idx != idx);
+        for cached_idx in cached.iter_mut() {
+            if *cached_idx > idx {
+                *cached_idx -= 1;
+            }
+        }
+        Ok(testcase)
+    }
+
+    fn get(&self, idx: usize) -> Result<&RefCell<Testcase<I>>, Error> {
+        let testcase = self.entries.get(idx)?;
+        if testcase.borrow().input().is_none() {
+            let filename = testcase
+                .borrow()
+                .filename()
+                .clone()
+                .ok_or_else(|| Error::illegal_state("The testcase has no file to load"))?;
+            let bytes = read_seed(Path::new(&filename), self.compress)?;
+            *testcase.borrow_mut().input_mut() = Some(I::from_raw_bytes(bytes));
+            self.cache(idx)?;
+        }
+        Ok(testcase)
+    }
+
+    fn current(&self) -> &Option<usize> {
+        self.entries.current()
+    }
+
+    fn current_mut(&mut self) -> &mut Option<usize> {
+        self.entries.current_mut()
+    }
+}
+
+/// The corpus picked on the commandline
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "I: serde::de::DeserializeOwned")]
+pub(crate) enum RuntimeCorpus<I>
+where
+    I: Input,
+{
+    /// Keeps a part of the testcases in memory, and all of them on disk
+    Cached(CachedOnDiskCorpus<I>),
+    /// Keeps the testcases on disk only, loading them when they are fuzzed
+    OnDisk(OnDiskCorpus<I>),
+    /// Keeps a part of the testcases in memory, and the plain bytes of all of them on disk
+    Raw(RawOnDiskCorpus<I>),
+    /// Keeps all testcases in memory only, they are lost on exit
+    InMemory(InMemoryCorpus<I>),
+}
+
+impl<I> Corpus<I> for RuntimeCorpus<I>
+where
+    I: Input + HasTargetBytes + FromRawBytes,
+{
+    fn count(&self) -> usize {
+        dispatch!(self => [Cached, OnDisk, Raw, InMemory], |corpus| corpus.count())
+    }
+
+    fn add(&mut self, testcase: Testcase<I>) -> Result<usize, Error> {
+        dispatch!(self => [Cached, OnDisk, Raw, InMemory], |corpus| corpus.add(testcase))
+    }
+
+    fn replace(&mut self, idx: usize, testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        dispatch!(self => [Cached, OnDisk, Raw, InMemory], |corpus| corpus.replace(idx, testcase))
+    }
+
+    fn remove(&mut self, idx: usize) -> Result<Option<Testcase<I>>, Error> {
+        dispatch!(self => [Cached, OnDisk, Raw, InMemory], |corpus| corpus.remove(idx))
+    }
+
+    fn get(&self, idx: usize) -> Result<&RefCell<Testcase<I>>, Error> {
+        dispatch!(self => [Cached, OnDisk, Raw, InMemory], |corpus| corpus.get(idx))
+    }
+
+    fn current(&self) -> &Option<usize> {
+        dispatch!(self => [Cached, OnDisk, Raw, InMemory], |corpus| corpus.current())
+    }
+
+    fn current_mut(&mut self) -> &mut Option<usize> {
+        dispatch!(self => [Cached, OnDisk, Raw, InMemory], |corpus| corpus.current_mut())
+    }
+}
+
+/// Stores the solutions on disk, the timeouts in their own dir if there is one for them.
+/// The indexes count the solutions of both dirs in the order they were found.
+/// Like libFuzzer, the files are named `crash-<sha1>` and `timeout-<sha1>` after their bytes.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "I: serde::de::DeserializeOwned")]
+pub(crate) struct SolutionsCorpus<I>
+where
+    I: Input,
+{
+    crashes: OnDiskCorpus<I>,
+    timeouts: Option<OnDiskCorpus<I>>,
+    crashes_dir: PathBuf,
+    timeouts_dir: Option<PathBuf>,
+    /// Whether each solution is a timeout stored in `timeouts`
+    kinds: Vec<bool>,
+    current: Option<usize>,
+    /// Whether new crashes are queued in `to_minimize`, see `--auto-tmin`
+    minimize: bool,
+    /// The files of the crashes added since the last [`SolutionsCorpus::take_to_minimize`]
+    to_minimize: Vec<PathBuf>,
+}
+
+impl<I> SolutionsCorpus<I>
+where
+    I: Input,
+{
+    pub(crate) fn new(
+        crashes_dir: PathBuf,
+        timeouts_dir: Option<PathBuf>,
+        minimize: bool,
+    ) -> Result<Self, Error> {
+        let corpus = |dir: &PathBuf| {
+            OnDiskCorpus::new_save_meta(dir.clone(), Some(OnDiskMetadataFormat::JsonPretty))
+        };
+        Ok(Self {
+            crashes: corpus(&crashes_dir)?,
+            timeouts: timeouts_dir.as_ref().map(corpus).transpose()?,
+            crashes_dir,
+            timeouts_dir,
+            kinds: vec![],
+            current: None,
+            minimize,
+            to_minimize: vec![],
+        })
+    }
+
+    /// Returns the crashes to minimize that were added since the last call.
+    /// They are added from the crash handler, right before the client restarts, so the new
+    /// client finds them in the restored state.
+    pub(crate) fn take_to_minimize(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.to_minimize)
+    }
+
+    /// Returns whether the solution at `idx` is a timeout, and its index in its corpus
+    fn locate(&self, idx: usize) -> Result<(bool, usize), Error> {
+        let is_timeout = *self
+            .kinds
+            .get(idx)
+            .ok_or_else(|| Error::illegal_argument(format!("No solution at index {}", idx)))?;
+        let inner_idx = self.kinds[..idx]
+            .iter()
+            .filter(|&&kind| kind == is_timeout)
+            .count();
+        Ok((is_timeout, inner_idx))
+    }
+
+    fn corpus(&self, is_timeout: bool) -> &OnDiskCorpus<I> {
+        match &self.timeouts {
+            Some(timeouts) if is_timeout => timeouts,
+            _ => &self.crashes,
+        }
+    }
+
+    fn corpus_mut(&mut self, is_timeout: bool) -> &mut OnDiskCorpus<I> {
+        match &mut self.timeouts {
+            Some(timeouts) if is_timeout => timeouts,
+            _ => &mut self.crashes,
+        }
+    }
+}
+
+impl<I> Corpus<I> for SolutionsCorpus<I>
+where
+    I: Input + HasTargetBytes,
+{
+    fn count(&self) -> usize {
+        self.kinds.len()
+    }
+
+    fn add(&mut self, mut testcase: Testcase<I>) -> Result<usize, Error> {
+        // Set by the objective, that appends its metadata before the solution is added
+        let is_timeout = self.timeouts.is_some()
+            && testcase
+                .metadata()
+                .get::<CrashMetadata>()
+                .is_some_and(|metadata| metadata.exit_kind == SolutionKind::Timeout);
+        if testcase.filename().is_none() {
+            let input = testcase
+                .input()
+                .as_ref()
+                .ok_or_else(|| Error::illegal_argument("The solution has no input to save"))?;
+            let (dir, prefix) = match &self.timeouts_dir {
+                Some(timeouts_dir) if is_timeout => (timeouts_dir, "timeout"),
+                _ => (&self.crashes_dir, "crash"),
+            };
+            let name = solution_name(prefix, input.target_bytes().as_slice());
+            *testcase.filename_mut() = Some(dir.join(name).to_string_lossy().to_string());
+        }
+        if let (Some(provenance), Some(filename)) = (
+            testcase.metadata().get::<ProvenanceMetadata>(),
+            testcase.filename(),
+        ) {
+            provenance.write(Path::new(filename))?;
+        }
+        if self.minimize && !is_timeout {
+            if let Some(filename) = testcase.filename() {
+                self.to_minimize.push(PathBuf::from(filename));
+            }
+        }
+        self.corpus_mut(is_timeout).add(testcase)?;
+        self.kinds.push(is_timeout);
+        Ok(self.kinds.len() - 1)
+    }
+
+    fn replace(&mut self, idx: usize, testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
+        let (is_timeout, inner_idx) = self.locate(idx)?;
+        self.corpus_mut(is_timeout).replace(inner_idx, testcase)
+    }
+
+    fn remove(&mut self, idx: usize) -> Result<Option<Testcase<I>>, Error> {
+        let (is_timeout, inner_idx) = match self.locate(idx) {
+            Ok(location) => location,
+            Err(_) => return Ok(None),
+        };
+        let testcase = self.corpus_mut(is_timeout).remove(inner_idx)?;
+        self.kinds.remove(idx);
+        Ok(testcase)
+    }
+
+    fn get(&self, idx: usize) -> Result<&RefCell<Testcase<I>>, Error> {
+        let (is_timeout, inner_idx) = self.locate(idx)?;
+        self.corpus(is_timeout).get(inner_idx)
+    }
+
+    fn current(&self) -> &Option<usize> {
+        &self.current
+    }
+
+    fn current_mut(&mut self) -> &mut Option<usize> {
+        &mut self.current
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn raw_corpus(
+        dir: &Path,
+        cache_len: usize,
+        compress: bool,
+    ) -> RawOnDiskCorpus<GeneralizedInput> {
+        RawOnDiskCorpus::new(dir.to_path_buf(), cache_len, compress).unwrap()
+    }
+
+    fn bytes_of(corpus: &RawOnDiskCorpus<GeneralizedInput>, idx: usize) -> Vec<u8> {
+        // A single expression, the borrow of the testcase outlives the one of its input
+        corpus
+            .get(idx)
+            .unwrap()
+            .borrow()
+            .input()
+            .as_ref()
+            .unwrap()
+            .target_bytes()
+            .as_slice()
+            .to_vec()
+    }
+
+    #[test]
+    fn name_solutions_like_libfuzzer() {
+        assert_eq!(
+            solution_name("crash", b""),
+            "crash-da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+        assert_eq!(
+            solution_name("timeout", b"abc"),
+            "timeout-a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+    }
+
+    #[test]
+    fn raw_corpus_writes_plain_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut corpus = raw_corpus(dir.path(), 8, false);
+        let idx = corpus
+            .add(Testcase::new(GeneralizedInput::new(b"plain".to_vec())))
+            .unwrap();
+        let filename = corpus
+            .get(idx)
+            .unwrap()
+            .borrow()
+            .filename()
+            .clone()
+            .unwrap();
+        assert!(Path::new(&filename).starts_with(dir.path()));
+        assert_eq!(fs::read(&filename).unwrap(), b"plain");
+    }
+
+    #[test]
+    fn raw_corpus_compresses_into_gz_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut corpus = raw_corpus(dir.path(), 8, true);
+        let idx = corpus
+            .add(Testcase::new(GeneralizedInput::new(b"packed".to_vec())))
+            .unwrap();
+        let filename = corpus
+            .get(idx)
+            .unwrap()
+            .borrow()
+            .filename()
+            .clone()
+            .unwrap();
+        assert!(filename.ends_with(".gz"));
+        assert_ne!(fs::read(&filename).unwrap(), b"packed");
+        assert_eq!(read_seed(Path::new(&filename), true).unwrap(), b"packed");
+        // Without --compress-corpus, the entries are read as they are
+        assert_ne!(read_seed(Path::new(&filename), false).unwrap(), b"packed");
+    }
+
+    #[test]
+    fn raw_corpus_keeps_cache_len_inputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut corpus = raw_corpus(dir.path(), 1, false);
+        for bytes in [b"first", b"other"] {
+            corpus
+                .add(Testcase::new(GeneralizedInput::new(bytes.to_vec())))
+                .unwrap();
+        }
+        assert!(corpus.entries.get(0).unwrap().borrow().input().is_none());
+        assert!(corpus.entries.get(1).unwrap().borrow().input().is_some());
+
+        // Loading the first one again evicts the other one
+        assert_eq!(bytes_of(&corpus, 0), b"first");
+        assert!(corpus.entries.get(1).unwrap().borrow().input().is_none());
+        assert_eq!(bytes_of(&corpus, 1), b"other");
+
+        corpus.remove(0).unwrap();
+        assert_eq!(corpus.count(), 1);
+        assert_eq!(bytes_of(&corpus, 0), b"other");
+    }
+}
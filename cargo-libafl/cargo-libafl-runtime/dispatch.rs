@@ -0,0 +1,27 @@
+//! Forwarding the trait methods of the enums that wrap the implementation picked on the
+//! commandline, like [`crate::corpus::RuntimeCorpus`], to the wrapped value
+
+/// Matches `$value`, a `Self` enum whose variants each wrap a single value, against the listed
+/// variants, and evaluates `$body` with that value bound to `$inner`, e.g.
+/// `dispatch!(self => [Queue, Weighted], |scheduler| scheduler.next(state))`.
+/// With `=> $wrapper`, the result is wrapped in the variant of the same name of `$wrapper`,
+/// e.g. a shared memory in the variant of the provider that created it.
+/// Variants can have attributes like `#[cfg(...)]`, the match stays exhaustive.
+macro_rules! dispatch {
+    (
+        $value:expr => [$($(#[$meta:meta])* $variant:ident),+ $(,)?],
+        |$inner:ident| $body:expr
+    ) => {
+        match $value {
+            $($(#[$meta])* Self::$variant($inner) => $body,)+
+        }
+    };
+    (
+        $value:expr => [$($(#[$meta:meta])* $variant:ident),+ $(,)?] => $wrapper:ident,
+        |$inner:ident| $body:expr
+    ) => {
+        match $value {
+            $($(#[$meta])* Self::$variant($inner) => $wrapper::$variant($body),)+
+        }
+    };
+}
@@ -0,0 +1,334 @@
+//! The monitor picked on the commandline, and the wrappers around it that log, record and
+//! summarize the stats of the clients
+
+use core::{cell::RefCell, time::Duration};
+use std::{
+    fs,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use libafl::{
+    bolts::current_time,
+    monitors::{ClientStats, Monitor, SimpleMonitor, UserStats},
+};
+
+#[cfg(feature = "tui")]
+use libafl::monitors::tui::TuiMonitor;
+
+/// Prints the monitor output line by line
+pub(crate) fn print_monitor_line(line: String) {
+    println!("{}", line);
+}
+
+/// The monitor picked on the commandline
+#[derive(Clone)]
+pub(crate) enum RuntimeMonitor {
+    Simple(SimpleMonitor<fn(String)>),
+    #[cfg(feature = "tui")]
+    Tui(TuiMonitor),
+}
+
+impl Monitor for RuntimeMonitor {
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        dispatch!(self => [Simple, #[cfg(feature = "tui")] Tui], |monitor| {
+            monitor.client_stats_mut()
+        })
+    }
+
+    fn client_stats(&self) -> &[ClientStats] {
+        dispatch!(self => [Simple, #[cfg(feature = "tui")] Tui], |monitor| monitor.client_stats())
+    }
+
+    fn start_time(&mut self) -> Duration {
+        dispatch!(self => [Simple, #[cfg(feature = "tui")] Tui], |monitor| monitor.start_time())
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        dispatch!(self => [Simple, #[cfg(feature = "tui")] Tui], |monitor| {
+            monitor.display(event_msg, sender_id)
+        })
+    }
+}
+
+/// The final stats of a run, aggregated over all clients
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct RunSummary {
+    pub(crate) elapsed: u64,
+    pub(crate) executions: u64,
+    pub(crate) exec_sec: u64,
+    pub(crate) corpus: u64,
+    pub(crate) solutions: u64,
+    /// The most edges any single client has hit, out of `total_edges`
+    pub(crate) edges: u64,
+    pub(crate) total_edges: u64,
+}
+
+impl RunSummary {
+    /// Writes the summary to `path` as a single JSON object
+    pub(crate) fn write(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(
+            path,
+            format!(
+                "{{\"elapsed\":{},\"executions\":{},\"exec_sec\":{},\"corpus\":{},\"solutions\":{},\"edges\":{},\"total_edges\":{}}}\n",
+                self.elapsed,
+                self.executions,
+                self.exec_sec,
+                self.corpus,
+                self.solutions,
+                self.edges,
+                self.total_edges
+            ),
+        )
+    }
+}
+
+/// Wraps a monitor and appends its stats to a file as newline-delimited JSON records.
+/// It also keeps the latest stats in `summary`, for the record written at the end of the run.
+#[derive(Clone)]
+pub(crate) struct JsonStatsMonitor<M> {
+    base: M,
+    path: Option<PathBuf>,
+    interval: Duration,
+    last_update: Duration,
+    summary: Rc<RefCell<Option<RunSummary>>>,
+}
+
+impl<M> JsonStatsMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`JsonStatsMonitor`], writing nothing if `path` is `None`
+    pub(crate) fn new(
+        base: M,
+        path: Option<PathBuf>,
+        interval: Duration,
+        summary: Rc<RefCell<Option<RunSummary>>>,
+    ) -> Self {
+        Self {
+            base,
+            path,
+            interval,
+            last_update: current_time(),
+            summary,
+        }
+    }
+
+    fn update_summary(&mut self) {
+        let (edges, total_edges) = self
+            .base
+            .client_stats()
+            .iter()
+            .flat_map(|client| client.user_monitor.iter())
+            .filter_map(|(name, stats)| match stats {
+                UserStats::Ratio(hit, total) if name.ends_with("edges") => Some((*hit, *total)),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+        *self.summary.borrow_mut() = Some(RunSummary {
+            elapsed: (current_time() - self.base.start_time()).as_secs(),
+            executions: self.base.total_execs(),
+            exec_sec: self.base.execs_per_sec(),
+            corpus: self.base.corpus_size(),
+            solutions: self.base.objective_size(),
+            edges,
+            total_edges,
+        });
+    }
+
+    fn write_record(&mut self, path: &Path) -> std::io::Result<()> {
+        let elapsed = (current_time() - self.base.start_time()).as_secs();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(
+            file,
+            "{{\"elapsed\":{},\"executions\":{},\"exec_sec\":{},\"corpus\":{},\"solutions\":{}}}",
+            elapsed,
+            self.base.total_execs(),
+            self.base.execs_per_sec(),
+            self.base.corpus_size(),
+            self.base.objective_size()
+        )
+    }
+}
+
+impl<M> Monitor for JsonStatsMonitor<M>
+where
+    M: Monitor,
+{
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        self.base.display(event_msg, sender_id);
+        self.update_summary();
+
+        let cur_time = current_time();
+        if cur_time - self.last_update < self.interval {
+            return;
+        }
+        self.last_update = cur_time;
+        if let Some(path) = self.path.clone() {
+            if let Err(e) = self.write_record(&path) {
+                eprintln!("Failed to write the stats to {:?}: {}", &path, e);
+            }
+        }
+    }
+}
+
+/// Wraps a monitor and prints the share of the time spent in each stage, summed over the
+/// `stage <name>` stats of all clients, for `--profile-stages`
+#[derive(Clone)]
+pub(crate) struct StageTimesMonitor<M> {
+    base: M,
+    enabled: bool,
+    interval: Duration,
+    last_update: Duration,
+}
+
+impl<M> StageTimesMonitor<M>
+where
+    M: Monitor,
+{
+    pub(crate) fn new(base: M, enabled: bool, interval: Duration) -> Self {
+        Self {
+            base,
+            enabled,
+            interval,
+            last_update: current_time(),
+        }
+    }
+
+    fn stage_times(&self) -> Vec<(String, u64)> {
+        let mut times: Vec<(String, u64)> = vec![];
+        for (name, stats) in self
+            .base
+            .client_stats()
+            .iter()
+            .flat_map(|client| client.user_monitor.iter())
+        {
+            let (Some(stage), UserStats::Number(micros)) = (name.strip_prefix("stage "), stats)
+            else {
+                continue;
+            };
+            match times.iter_mut().find(|(name, _)| name == stage) {
+                Some((_, total)) => *total += micros,
+                None => times.push((stage.to_string(), *micros)),
+            }
+        }
+        times.sort_by(|a, b| b.1.cmp(&a.1));
+        times
+    }
+}
+
+impl<M> Monitor for StageTimesMonitor<M>
+where
+    M: Monitor,
+{
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        self.base.display(event_msg, sender_id);
+
+        let cur_time = current_time();
+        if !self.enabled || cur_time - self.last_update < self.interval {
+            return;
+        }
+        self.last_update = cur_time;
+        let times = self.stage_times();
+        let total: u64 = times.iter().map(|(_, micros)| micros).sum();
+        if total == 0 {
+            return;
+        }
+        let shares: Vec<String> = times
+            .iter()
+            .map(|(stage, micros)| {
+                format!("{} {:.1}%", stage, *micros as f64 * 100.0 / total as f64)
+            })
+            .collect();
+        println!("[Stages] {}", shares.join(", "));
+    }
+}
+
+/// Wraps a monitor and also appends its lines to a log file, prefixed with the unix time,
+/// so that they outlive the terminal session and contain no terminal control characters
+#[derive(Clone)]
+pub(crate) struct LogFileMonitor<M> {
+    base: M,
+    path: Option<PathBuf>,
+}
+
+impl<M> LogFileMonitor<M>
+where
+    M: Monitor,
+{
+    /// Creates a new [`LogFileMonitor`], writing nothing if `path` is `None`
+    pub(crate) fn new(base: M, path: Option<PathBuf>) -> Self {
+        Self { base, path }
+    }
+
+    fn write_line(&mut self, path: &Path, event_msg: &str, sender_id: u32) -> std::io::Result<()> {
+        let elapsed = (current_time() - self.base.start_time()).as_secs();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(
+            file,
+            "{} [{} #{}] run time: {}s, clients: {}, corpus: {}, objectives: {}, executions: {}, exec/sec: {}",
+            current_time().as_secs(),
+            event_msg,
+            sender_id,
+            elapsed,
+            self.base.client_stats().len(),
+            self.base.corpus_size(),
+            self.base.objective_size(),
+            self.base.total_execs(),
+            self.base.execs_per_sec()
+        )
+    }
+}
+
+impl<M> Monitor for LogFileMonitor<M>
+where
+    M: Monitor,
+{
+    fn client_stats_mut(&mut self) -> &mut Vec<ClientStats> {
+        self.base.client_stats_mut()
+    }
+
+    fn client_stats(&self) -> &[ClientStats] {
+        self.base.client_stats()
+    }
+
+    fn start_time(&mut self) -> Duration {
+        self.base.start_time()
+    }
+
+    fn display(&mut self, event_msg: String, sender_id: u32) {
+        if let Some(path) = self.path.clone() {
+            if let Err(e) = self.write_line(&path, &event_msg, sender_id) {
+                eprintln!("Failed to write the log to {:?}: {}", &path, e);
+            }
+        }
+        self.base.display(event_msg, sender_id);
+    }
+}
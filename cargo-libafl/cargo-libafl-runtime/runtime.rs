@@ -1,32 +1,71 @@
 //! A libfuzzer-like fuzzer with llmp-multithreading support and restarts
 //! The `launcher` will spawn new processes for each cpu core.
-use mimalloc::MiMalloc;
+//! Besides the `main` linked into fuzz targets, [`run`] and [`Opt`] allow to start it from code.
 #[global_allocator]
-static GLOBAL: MiMalloc = MiMalloc;
+static GLOBAL: LimitedAllocator = LimitedAllocator;
+
+#[macro_use]
+mod dispatch;
+mod corpus;
+mod monitor;
+mod scheduler;
+mod shmem;
+mod stop;
+
+pub use corpus::CorpusFormat;
+pub use scheduler::{Favor, SchedulerKind};
+pub use shmem::ShMemKind;
 
 use clap::{self, Parser};
-use core::time::Duration;
-use std::{env, fs, net::SocketAddr, path::PathBuf};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::RefCell,
+    fmt::{Debug, Write as FmtWrite},
+    marker::PhantomData,
+    sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering},
+    time::Duration,
+};
+use std::{
+    collections::HashSet,
+    env, fs,
+    fs::OpenOptions,
+    io::{Read, Write},
+    net::SocketAddr,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Mutex,
+    thread,
+};
 
 use libafl::{
     bolts::{
         core_affinity::Cores,
-        current_nanos,
+        current_nanos, current_time,
         launcher::Launcher,
-        rands::StdRand,
+        rands::{Rand, StdRand},
         shmem::{ShMemProvider, StdShMemProvider},
-        tuples::{tuple_list, Merge},
-        AsSlice,
+        tuples::{tuple_list, MatchName, Merge, Named},
+        AsSlice, HasLen,
     },
-    corpus::{CachedOnDiskCorpus, Corpus, OnDiskCorpus},
-    events::EventConfig,
-    executors::{inprocess::InProcessExecutor, ExitKind, TimeoutExecutor},
-    feedback_and_fast, feedback_or,
-    feedbacks::{CrashFeedback, MaxMapFeedback, NewHashFeedback, TimeFeedback},
-    fuzzer::{Fuzzer, StdFuzzer},
-    generators::RandBytesGenerator,
-    inputs::HasTargetBytes,
-    monitors::tui::TuiMonitor,
+    corpus::{CachedOnDiskCorpus, Corpus, InMemoryCorpus, OnDiskCorpus, Testcase},
+    events::{
+        Event, EventConfig, EventFirer, EventRestarter, ProgressReporter, SimpleEventManager,
+    },
+    executors::{
+        inprocess::{InProcessExecutor, TimeoutInProcessForkExecutor},
+        Executor, ExitKind, HasObservers, TimeoutExecutor,
+    },
+    feedback_and_fast, feedback_or, feedback_or_fast,
+    feedbacks::{
+        ConstFeedback, CrashFeedback, CrashFeedbackFactory, Feedback, MaxMapFeedback,
+        NewHashFeedback, TimeFeedback, TimeoutFeedback,
+    },
+    fuzzer::{Evaluator, ExecutesInput, Fuzzer, StdFuzzer},
+    generators::{RandBytesGenerator, RandPrintablesGenerator},
+    impl_serdeany,
+    inputs::{BytesInput, HasBytesVec, HasTargetBytes, Input},
+    monitors::{SimpleMonitor, UserStats},
     mutators::{
         grimoire::{
             GrimoireExtensionMutator, GrimoireRandomDeleteMutator,
@@ -36,38 +75,867 @@ use libafl::{
         token_mutations::{I2SRandReplace, Tokens},
         StdMOptMutator,
     },
-    observers::{BacktraceObserver, HitcountsIterableMapObserver, MultiMapObserver, TimeObserver},
+    observers::{
+        BacktraceObserver, HarnessType, HitcountsIterableMapObserver, MultiMapObserver,
+        ObserversTuple, TimeObserver,
+    },
     prelude::{GeneralizedInput, GeneralizedInputBytesGenerator},
     schedulers::{
-        powersched::PowerSchedule, IndexesLenTimeMinimizerScheduler, PowerQueueScheduler,
+        powersched::{PowerSchedule, SchedulerMetadata},
+        QueueScheduler,
     },
     stages::{
-        calibrate::CalibrationStage, GeneralizationStage, SkippableStage, StdMutationalStage,
-        StdPowerMutationalStage, TracingStage,
+        calibrate::CalibrationStage, sync::SyncFromDiskStage, tmin::StdTMinMutationalStage,
+        GeneralizationStage, SkippableStage, Stage, StdMutationalStage, StdPowerMutationalStage,
+        TracingStage,
+    },
+    state::{
+        HasClientPerfMonitor, HasCorpus, HasExecutions, HasMaxSize, HasMetadata, HasRand,
+        HasSolutions, StdState,
     },
-    state::{HasCorpus, HasMetadata, StdState},
     Error,
 };
+use serde::{Deserialize, Serialize};
+
+use libafl_targets::{CmpLogObserver, CMPLOG_MAP, CMPLOG_MAP_H, CMPLOG_MAP_W, COUNTERS_MAPS};
+
+use corpus::{read_seed, RawOnDiskCorpus, RuntimeCorpus, SolutionsCorpus};
+use monitor::{
+    print_monitor_line, JsonStatsMonitor, LogFileMonitor, RuntimeMonitor, StageTimesMonitor,
+};
+use scheduler::{FavorScheduler, RuntimeScheduler};
+use shmem::shmem_provider;
+use stop::{Stop, StopCondition};
 
-use libafl_targets::{CmpLogObserver, CMPLOG_MAP, COUNTERS_MAPS};
+#[cfg(feature = "tui")]
+use libafl::monitors::tui::TuiMonitor;
 
 #[cfg(any(target_os = "linux", target_vendor = "apple"))]
 use libafl_targets::autotokens;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The exit code used when solutions were found, the same as libFuzzer's
+const SOLUTION_EXIT_CODE: i32 = 77;
+
+/// The exit code of libFuzzer for a crashing input, used by `--one-shot`
+const LIBFUZZER_CRASH_EXIT_CODE: i32 = 77;
+
+/// The exit code of libFuzzer for an input that timed out, used by `--one-shot`
+const LIBFUZZER_TIMEOUT_EXIT_CODE: i32 = 70;
+
+/// How often clients report their stats to the broker
+const MONITOR_TIMEOUT: Duration = Duration::from_secs(15);
+
 /// Parses a millseconds int into a [`Duration`], used for commandline arg parsing
 fn timeout_from_millis_str(time: &str) -> Result<Duration, Error> {
     Ok(Duration::from_millis(time.parse()?))
 }
 
+/// Parses a seconds int into a [`Duration`], used for commandline arg parsing
+fn duration_from_secs_str(time: &str) -> Result<Duration, Error> {
+    Ok(Duration::from_secs(time.parse()?))
+}
+
+/// Parses `on` or `off` into a [`bool`], used for commandline arg parsing
+fn on_off_from_str(toggle: &str) -> Result<bool, Error> {
+    match toggle {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(Error::illegal_argument(format!(
+            "Expected 'on' or 'off', got '{}'",
+            toggle
+        ))),
+    }
+}
+
+/// Parses the name of a [`PowerSchedule`], used for commandline arg parsing
+fn power_schedule_from_str(schedule: &str) -> Result<PowerSchedule, Error> {
+    match schedule {
+        "explore" => Ok(PowerSchedule::EXPLORE),
+        "fast" => Ok(PowerSchedule::FAST),
+        "coe" => Ok(PowerSchedule::COE),
+        "lin" => Ok(PowerSchedule::LIN),
+        "quad" => Ok(PowerSchedule::QUAD),
+        _ => Err(Error::illegal_argument(format!(
+            "Unknown power schedule '{}', expected one of explore, fast, coe, lin, quad",
+            schedule
+        ))),
+    }
+}
+
+/// Parses the power of two of the maximum number of stacked mutations, used for commandline arg parsing
+fn stack_pow_from_str(pow: &str) -> Result<u64, Error> {
+    match pow.parse()? {
+        pow @ 1..=10 => Ok(pow),
+        pow => Err(Error::illegal_argument(format!(
+            "Expected a stack power between 1 and 10, got {}",
+            pow
+        ))),
+    }
+}
+
+/// Parses the number of MOpt swarms, used for commandline arg parsing
+fn mopt_iterations_from_str(iterations: &str) -> Result<usize, Error> {
+    match iterations.parse()? {
+        iterations @ 1..=64 => Ok(iterations),
+        iterations => Err(Error::illegal_argument(format!(
+            "Expected between 1 and 64 MOpt iterations, got {}",
+            iterations
+        ))),
+    }
+}
+
+/// Selects the first `workers` available cores
+fn workers_to_cores(workers: usize) -> Result<Cores, Error> {
+    let mut cores = Cores::from_cmdline("all")?;
+    if workers == 0 || workers > cores.ids.len() {
+        return Err(Error::illegal_argument(format!(
+            "between 1 and {} workers are supported on this machine",
+            cores.ids.len()
+        )));
+    }
+    cores.ids.truncate(workers);
+    cores.cmdline = format!("{} workers", workers);
+    Ok(cores)
+}
+
+/// Counts the solutions, or corpus entries, stored in `dir`, skipping the hidden metadata and
+/// lock files
+fn count_solutions(dir: &Path) -> usize {
+    fs::read_dir(dir).map_or(0, |entries| {
+        entries
+            .flatten()
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .count()
+    })
+}
+
+/// Lists the files in `dir` and its subdirs, without the hidden ones, e.g. metadata files
+fn seed_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut files = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(seed_files(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Returns the directories holding the crashes and timeouts, one pair per client with the AFL++ layout
+fn solutions_dirs(output_dir: &Path, afl_layout: bool) -> Vec<PathBuf> {
+    let dirs = if afl_layout {
+        fs::read_dir(output_dir).map_or_else(
+            |_| vec![],
+            |entries| entries.flatten().map(|entry| entry.path()).collect(),
+        )
+    } else {
+        vec![output_dir.to_path_buf()]
+    };
+    dirs.iter()
+        .flat_map(|dir| [dir.join("crashes"), dir.join("timeouts")])
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// Returns the file name of the fuzz target binary, which is the name of the target
+fn target_name() -> String {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| {
+            exe.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_default()
+}
+
+/// Formats the current time as an RFC 3339 timestamp in UTC, e.g. `2022-09-01T12:00:00Z`
+fn rfc3339_now() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (days, time) = (secs / 86400, secs % 86400);
+    // The civil date of the days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Seed archives unpacked into a temporary directory, which is removed again on drop.
+/// Only the process that unpacked them cleans up, not the forked clients.
+struct ExtractedArchives {
+    dir: Option<PathBuf>,
+    owner: u32,
+}
+
+impl ExtractedArchives {
+    /// Unpacks the `.tar`, `.tar.gz`/`.tgz` and `.zip` files in `inputs`,
+    /// and replaces them by the directories they were unpacked to
+    fn extract(inputs: &mut [PathBuf]) -> Result<Self, Error> {
+        let mut extracted = Self {
+            dir: None,
+            owner: std::process::id(),
+        };
+        for (i, input) in inputs.iter_mut().enumerate() {
+            let name = input.to_string_lossy().to_lowercase();
+            let is_gz = name.ends_with(".tar.gz") || name.ends_with(".tgz");
+            if !is_gz && !name.ends_with(".tar") && !name.ends_with(".zip") {
+                continue;
+            }
+
+            if extracted.dir.is_none() {
+                extracted.dir = Some(tempfile::tempdir()?.into_path());
+            }
+            let dest = extracted.dir.as_ref().unwrap().join(i.to_string());
+            fs::create_dir(&dest)?;
+
+            let file = fs::File::open(&input)?;
+            if name.ends_with(".zip") {
+                zip::ZipArchive::new(file)
+                    .and_then(|mut archive| archive.extract(&dest))
+                    .map_err(|err| {
+                        Error::illegal_argument(format!("Failed to unpack {:?}: {}", input, err))
+                    })?;
+            } else if is_gz {
+                tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(&dest)?;
+            } else {
+                tar::Archive::new(file).unpack(&dest)?;
+            }
+            println!("Unpacked the seeds from {:?}", input);
+            *input = dest;
+        }
+        Ok(extracted)
+    }
+}
+
+impl Drop for ExtractedArchives {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.dir {
+            if std::process::id() == self.owner {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+    }
+}
+
+/// Replaces AFL++ output dirs, holding a `queue`, and sync dirs, holding several of them,
+/// by the `queue` dirs they contain. Other dirs are returned as they are.
+fn expand_afl_queues(dir: PathBuf) -> Vec<PathBuf> {
+    if dir.join("queue").is_dir() {
+        return vec![dir.join("queue")];
+    }
+    let queues: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path().join("queue"))
+                .filter(|queue| queue.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    if queues.is_empty() {
+        vec![dir]
+    } else {
+        queues
+    }
+}
+
+/// Logs the number and combined size of the coverage maps registered by the instrumentation,
+/// warning if they add up to less than `expected_size` entries.
+fn report_coverage_maps(expected_size: Option<usize>) {
+    let maps = unsafe { &COUNTERS_MAPS };
+    let size: usize = maps.iter().map(|map| map.len()).sum();
+    println!(
+        "Coverage: {} maps with {} entries in total",
+        maps.len(),
+        size
+    );
+
+    if maps.is_empty() {
+        eprintln!("Warning: no coverage maps found, is the target built with instrumentation?");
+    } else if let Some(expected_size) = expected_size {
+        if size < expected_size {
+            eprintln!(
+                "Warning: the coverage maps have {} entries, less than the expected {}. Parts of the target may not be instrumented",
+                size, expected_size
+            );
+        }
+    }
+}
+
+/// Prints the observers the clients will register and the maps behind them, for `--dump-observers`
+fn dump_observers(cmplog_enabled: bool, backtrace_dedup: bool) {
+    let maps = unsafe { &COUNTERS_MAPS };
+    println!("Observers:");
+    println!(
+        "  edges: {} entries in {} maps of COUNTERS_MAPS",
+        maps.iter().map(|map| map.len()).sum::<usize>(),
+        maps.len()
+    );
+    for (i, map) in maps.iter().enumerate() {
+        println!("    map {}: {} entries", i, map.len());
+    }
+    println!("  time: execution time of each run");
+    println!(
+        "  cmplog: {}x{} entries in CMPLOG_MAP, {}",
+        CMPLOG_MAP_W,
+        CMPLOG_MAP_H,
+        if cmplog_enabled {
+            "used by the tracing stage"
+        } else {
+            "unused without --cmplog"
+        }
+    );
+    println!(
+        "  BacktraceObserver: {}",
+        if backtrace_dedup {
+            "hashes the backtrace of each crash"
+        } else {
+            "inactive with --no-backtrace-dedup"
+        }
+    );
+}
+
+/// Loads the tokens from the given dictionary files, plus the ones found by `autotokens` if
+/// enabled. With `max_tokens`, only the first tokens are kept, the ones from the files first.
+fn load_tokens(
+    token_files: &[PathBuf],
+    inline_tokens: &[Vec<u8>],
+    use_autotokens: bool,
+    max_tokens: Option<usize>,
+) -> Result<Tokens, Error> {
+    let mut toks = Tokens::default();
+    for tokenfile in token_files {
+        toks.add_from_file(tokenfile)?;
+    }
+    for token in inline_tokens {
+        toks.add_token(token);
+    }
+    #[cfg(any(target_os = "linux", target_vendor = "apple"))]
+    {
+        if use_autotokens {
+            toks += autotokens()?;
+        }
+    }
+    #[cfg(not(any(target_os = "linux", target_vendor = "apple")))]
+    let _ = use_autotokens;
+
+    match max_tokens {
+        Some(max_tokens) if toks.len() > max_tokens => {
+            let mut capped = Tokens::default();
+            for token in toks.tokens().iter().take(max_tokens) {
+                capped.add_token(token);
+            }
+            Ok(capped)
+        }
+        _ => Ok(toks),
+    }
+}
+
+/// Concatenates a few random tokens into an input of at most `max_size` bytes, a better start
+/// than random bytes for text and format parsers
+fn token_seed<R: Rand>(rand: &mut R, tokens: &[Vec<u8>], max_size: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for _ in 0..=rand.below(8) {
+        let token = &tokens[rand.below(tokens.len() as u64) as usize];
+        if bytes.len() + token.len() > max_size {
+            break;
+        }
+        bytes.extend_from_slice(token);
+    }
+    bytes
+}
+
+/// Decodes a token given with `--token`, escaped like the values of an AFL dictionary:
+/// `\\`, `\"` and `\xNN` for any byte
+fn unescape_token(token: &str) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::illegal_argument(format!("Invalid escape sequence in token {}", token));
+    let mut bytes = Vec::with_capacity(token.len());
+    let mut rest = token.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match rest {
+            [escaped @ (b'\\' | b'"'), tail @ ..] => {
+                bytes.push(*escaped);
+                rest = tail;
+            }
+            [b'x', hex @ ..] if hex.len() >= 2 => {
+                let hex = std::str::from_utf8(&hex[..2]).map_err(|_| invalid())?;
+                bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                rest = &rest[3..];
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(bytes)
+}
+
+/// Writes the tokens in the AFL dictionary format, so that `-x` can load them back
+fn write_dictionary(tokens: &Tokens, path: &Path) -> Result<(), Error> {
+    let mut dict = String::new();
+    for (i, token) in tokens.tokens().iter().enumerate() {
+        dict.push_str(&format!("token_{}=\"", i));
+        for &byte in token {
+            if (byte.is_ascii_graphic() && byte != b'"' && byte != b'\\') || byte == b' ' {
+                dict.push(byte as char);
+            } else {
+                dict.push_str(&format!("\\x{:02X}", byte));
+            }
+        }
+        dict.push_str("\"\n");
+    }
+    fs::write(path, dict)?;
+    Ok(())
+}
+
+/// The time spent in each stage of a client, in microseconds, for `--profile-stages`.
+/// It is kept in the state, so that it adds up across restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StageTimesMetadata {
+    micros: Vec<(String, u64)>,
+}
+
+impl_serdeany!(StageTimesMetadata);
+
+/// How many of the `--import-solutions` files a client has replayed. It is counted before
+/// each one runs, so that a client restarted by a crashing file goes on with the next one.
+#[derive(Debug, Serialize, Deserialize)]
+struct ImportedSolutionsMetadata {
+    imported: usize,
+}
+
+impl_serdeany!(ImportedSolutionsMetadata);
+
+/// Measures the time spent in the wrapped stage into the [`StageTimesMetadata`]
+struct TimedStage<ST> {
+    name: &'static str,
+    inner: ST,
+    enabled: bool,
+}
+
+impl<ST> TimedStage<ST> {
+    fn new(name: &'static str, inner: ST, enabled: bool) -> Self {
+        Self {
+            name,
+            inner,
+            enabled,
+        }
+    }
+}
+
+impl<E, EM, S, Z, ST> Stage<E, EM, S, Z> for TimedStage<ST>
+where
+    ST: Stage<E, EM, S, Z>,
+    S: HasMetadata,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut S,
+        manager: &mut EM,
+        corpus_idx: usize,
+    ) -> Result<(), Error> {
+        if !self.enabled {
+            return self
+                .inner
+                .perform(fuzzer, executor, state, manager, corpus_idx);
+        }
+        let start = current_time();
+        let result = self
+            .inner
+            .perform(fuzzer, executor, state, manager, corpus_idx);
+        let elapsed = (current_time() - start).as_micros() as u64;
+        if state.metadata().get::<StageTimesMetadata>().is_none() {
+            state.add_metadata(StageTimesMetadata::default());
+        }
+        let times = state
+            .metadata_mut()
+            .get_mut::<StageTimesMetadata>()
+            .unwrap();
+        match times.micros.iter_mut().find(|(name, _)| name == self.name) {
+            Some((_, micros)) => *micros += elapsed,
+            None => times.micros.push((self.name.to_string(), elapsed)),
+        }
+        result
+    }
+}
+
+/// How a solution exited, saved as the name of the [`ExitKind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum SolutionKind {
+    Crash,
+    Oom,
+    Timeout,
+    /// Any other exit the objective considered a solution
+    Other,
+}
+
+impl From<&ExitKind> for SolutionKind {
+    fn from(exit_kind: &ExitKind) -> Self {
+        match exit_kind {
+            ExitKind::Crash => SolutionKind::Crash,
+            ExitKind::Oom => SolutionKind::Oom,
+            ExitKind::Timeout => SolutionKind::Timeout,
+            _ => SolutionKind::Other,
+        }
+    }
+}
+
+/// Triage information about a crash, stored in the hidden `.<name>.metadata` file next to it
+#[derive(Debug, Serialize, Deserialize)]
+struct CrashMetadata {
+    exit_kind: SolutionKind,
+    backtrace: String,
+}
+
+impl_serdeany!(CrashMetadata);
+
+/// Attaches [`CrashMetadata`] and the execution time to new solutions.
+/// It never rejects an input, so it goes last in the objective.
+#[derive(Debug, Default)]
+struct CrashMetadataFeedback {
+    exit_kind: Option<SolutionKind>,
+    exec_time: Option<Duration>,
+    backtrace: Option<String>,
+    /// Leaves the backtrace empty, for platforms where unwinding is slow or unreliable
+    skip_backtrace: bool,
+}
+
+impl Named for CrashMetadataFeedback {
+    fn name(&self) -> &str {
+        "CrashMetadataFeedback"
+    }
+}
+
+impl<I, S> Feedback<I, S> for CrashMetadataFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        self.exit_kind = Some(exit_kind.into());
+        self.exec_time = observers
+            .match_name::<TimeObserver>("time")
+            .and_then(|observer| *observer.last_runtime());
+        // The objective runs inside the crash handler, so this still has the frames of the crash
+        if !self.skip_backtrace {
+            self.backtrace = Some(std::backtrace::Backtrace::force_capture().to_string());
+        }
+        Ok(true)
+    }
+
+    fn append_metadata(&mut self, _state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        *testcase.exec_time_mut() = self.exec_time.take();
+        testcase.add_metadata(CrashMetadata {
+            exit_kind: self.exit_kind.take().unwrap_or(SolutionKind::Other),
+            backtrace: self.backtrace.take().unwrap_or_default(),
+        });
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &I) -> Result<(), Error> {
+        self.exit_kind = None;
+        self.exec_time = None;
+        self.backtrace = None;
+        Ok(())
+    }
+}
+
+/// A corpus entry an input descends from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProvenanceEntry {
+    id: usize,
+    file: Option<String>,
+}
+
+/// The corpus entries an input was mutated from, its parent last, recorded with `--trace-provenance`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProvenanceMetadata {
+    ancestors: Vec<ProvenanceEntry>,
+}
+
+impl_serdeany!(ProvenanceMetadata);
+
+impl ProvenanceMetadata {
+    /// Writes the ancestors to the hidden `.<name>.provenance` file next to the solution `filename`
+    fn write(&self, filename: &Path) -> std::io::Result<()> {
+        let name = filename
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut provenance = format!(
+            "# The corpus entries {} was mutated from, starting with the seed or generated input\n",
+            name
+        );
+        for ancestor in &self.ancestors {
+            provenance.push_str(&format!(
+                "{} {}\n",
+                ancestor.id,
+                ancestor.file.as_deref().unwrap_or("<in memory>")
+            ));
+        }
+        fs::write(
+            filename.with_file_name(format!(".{}.provenance", name)),
+            provenance,
+        )
+    }
+}
+
+/// Attaches [`ProvenanceMetadata`] to new corpus entries and solutions, from the corpus entry
+/// being fuzzed. Best-effort, as it does not know the mutations that were applied.
+/// It never decides if an input is interesting on its own.
+#[derive(Debug)]
+struct ProvenanceFeedback {
+    enabled: bool,
+}
+
+impl Named for ProvenanceFeedback {
+    fn name(&self) -> &str {
+        "ProvenanceFeedback"
+    }
+}
+
+impl<I, S> Feedback<I, S> for ProvenanceFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor + HasCorpus<I>,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata(&mut self, state: &mut S, testcase: &mut Testcase<I>) -> Result<(), Error> {
+        if !self.enabled {
+            return Ok(());
+        }
+        // Seeds and generated inputs are added before any entry is fuzzed
+        let Some(parent_id) = *state.corpus().current() else {
+            return Ok(());
+        };
+        let parent = state.corpus().get(parent_id)?.borrow();
+        let mut ancestors = parent
+            .metadata()
+            .get::<ProvenanceMetadata>()
+            .map_or_else(Vec::new, |provenance| provenance.ancestors.clone());
+        ancestors.push(ProvenanceEntry {
+            id: parent_id,
+            file: parent.filename().clone(),
+        });
+        drop(parent);
+        testcase.add_metadata(ProvenanceMetadata { ancestors });
+        Ok(())
+    }
+}
+
+/// Rejects crashes whose backtrace hash is listed in the hashes file, and adds new ones to it.
+/// Unlike [`NewHashFeedback`], the hashes are shared by all clients and survive restarts.
+/// Without a hashes file every crash is accepted.
+#[derive(Debug)]
+struct UniqueCrashFeedback {
+    hashes_file: Option<PathBuf>,
+}
+
+impl Named for UniqueCrashFeedback {
+    fn name(&self) -> &str {
+        "UniqueCrashFeedback"
+    }
+}
+
+impl<I, S> Feedback<I, S> for UniqueCrashFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        let Some(hashes_file) = &self.hashes_file else {
+            return Ok(true);
+        };
+        // Filled in by the backtrace observer of the in-process executor
+        let Some(hash) = (unsafe { BACKTRACE }) else {
+            return Ok(true);
+        };
+
+        // Lock the file from the lookup to the append, else two clients hitting the same crash
+        // at once would both keep it. The lock is released when the file is closed.
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(hashes_file)?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut seen = String::new();
+        file.read_to_string(&mut seen)?;
+        let line = format!("{:016x}", hash);
+        if seen.lines().any(|seen_line| seen_line == line) {
+            return Ok(false);
+        }
+        writeln!(file, "{}", line)?;
+        Ok(true)
+    }
+}
+
+/// Rejects crashes whose backtrace contains any of the patterns, e.g. for bugs that are already known.
+/// Without patterns every crash is accepted.
+#[derive(Debug)]
+struct IgnoreCrashFeedback {
+    patterns: Vec<String>,
+}
+
+impl Named for IgnoreCrashFeedback {
+    fn name(&self) -> &str {
+        "IgnoreCrashFeedback"
+    }
+}
+
+impl<I, S> Feedback<I, S> for IgnoreCrashFeedback
+where
+    I: Input,
+    S: HasClientPerfMonitor,
+{
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &I,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<I>,
+        OT: ObserversTuple<I, S>,
+    {
+        if self.patterns.is_empty() {
+            return Ok(true);
+        }
+        // Like for the crash metadata, this runs inside the crash handler
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        Ok(!self
+            .patterns
+            .iter()
+            .any(|pattern| backtrace.contains(pattern.as_str())))
+    }
+}
+
+/// A [`TimeoutExecutor`] whose timeout grows with the length of the input, by `per_kb` for
+/// each KiB, for targets whose run time is linear in it. Without `per_kb` it is the base one.
+#[derive(Debug)]
+struct ScalingTimeoutExecutor<E> {
+    inner: TimeoutExecutor<E>,
+    base: Duration,
+    per_kb: Option<Duration>,
+}
+
+impl<E, EM, I, S, Z> Executor<EM, I, S, Z> for ScalingTimeoutExecutor<E>
+where
+    E: Debug,
+    I: Input + HasLen,
+    TimeoutExecutor<E>: Executor<EM, I, S, Z>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut S,
+        mgr: &mut EM,
+        input: &I,
+    ) -> Result<ExitKind, Error> {
+        if let Some(per_kb) = self.per_kb {
+            let extra = per_kb.as_micros() * input.len() as u128 / 1024;
+            self.inner
+                .set_timeout(self.base + Duration::from_micros(extra as u64));
+        }
+        self.inner.run_target(fuzzer, state, mgr, input)
+    }
+}
+
+impl<E, I, OT, S> HasObservers<I, OT, S> for ScalingTimeoutExecutor<E>
+where
+    E: Debug,
+    I: Input,
+    OT: ObserversTuple<I, S>,
+    TimeoutExecutor<E>: HasObservers<I, OT, S>,
+{
+    fn observers(&self) -> &OT {
+        self.inner.observers()
+    }
+
+    fn observers_mut(&mut self) -> &mut OT {
+        self.inner.observers_mut()
+    }
+}
+
+/// The options of the fuzzer, parsed from the commandline by [`main`].
+/// Embedders can build them in code instead, starting from [`Opt::default`], and pass them to [`run`].
 #[derive(Parser, Debug)]
 #[command(
     name = "cargo-libafl",
     about = "A `cargo` wrapper to fuzz Rust code with `LibAFL`",
     author = "Andrea Fioraldi <andreafioraldi@gmail.com> and the LibAFL team"
 )]
-struct Opt {
+pub struct Opt {
     #[arg(
         short,
         long,
@@ -76,7 +944,16 @@ struct Opt {
         default_value = "1",
         name = "CORES"
     )]
-    cores: Cores,
+    pub cores: Cores,
+
+    #[arg(
+        short = 'j',
+        long,
+        help = "Spawn the given number of clients, bound to the first available cores. Use --cores instead to pick the cores",
+        name = "WORKERS",
+        conflicts_with = "CORES"
+    )]
+    pub workers: Option<usize>,
 
     #[arg(
         short = 'p',
@@ -84,22 +961,65 @@ struct Opt {
         help = "Choose the broker TCP port, otherwise pick one at random",
         name = "PORT"
     )]
-    broker_port: Option<u16>,
+    pub broker_port: Option<u16>,
 
     #[arg(short = 'a', long, help = "Specify a remote broker", name = "REMOTE")]
-    remote_broker_addr: Option<SocketAddr>,
+    pub remote_broker_addr: Option<SocketAddr>,
 
-    #[arg(short, long, help = "Set an initial corpus directory", name = "INPUT")]
-    input: Vec<PathBuf>,
+    #[arg(
+        long,
+        help = "Name the configuration shared with the other clients of the broker, instead of deriving it from the build id. All hosts fuzzing together must use the same name and targets with compatible coverage maps",
+        name = "CONFIG_NAME"
+    )]
+    pub config_name: Option<String>,
 
     #[arg(
         short,
         long,
-        help = "Set the output directory, default is ./out",
+        help = "Set an initial corpus directory or a single seed file. A .tar, .tar.gz or .zip archive is unpacked to a temporary directory first",
+        name = "INPUT"
+    )]
+    pub input: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Import the --input seeds into <OUTPUT>/corpus and exit without fuzzing. The seeds are run from the smallest to the largest, and only the ones that add coverage are kept",
+        name = "IMPORT_ONLY",
+        requires = "INPUT",
+        conflicts_with_all = ["AFL_LAYOUT", "IN_MEMORY_CORPUS"]
+    )]
+    pub import_only: bool,
+
+    #[arg(
+        long,
+        help = "Replay the solutions of an earlier session from the given crashes directory at startup, e.g. to check a fix. The ones that add coverage go to the corpus, and the ones that still crash or time out are saved as solutions again",
+        name = "IMPORT_SOLUTIONS"
+    )]
+    pub import_solutions: Option<PathBuf>,
+
+    #[arg(
+        short,
+        long,
+        help = "Set the output directory, default is ./out. A summary.json with the final stats is written there when fuzzing ends",
         name = "OUTPUT",
         default_value = "./out"
     )]
-    output: PathBuf,
+    pub output: PathBuf,
+
+    #[arg(
+        long,
+        help = "Write to a new <OUTPUT>/<target>-<timestamp> dir on each run, instead of reusing the output directory",
+        name = "TIMESTAMP_OUTPUT"
+    )]
+    pub timestamp_output: bool,
+
+    #[arg(
+        long,
+        help = "Point the <OUTPUT>/latest symlink to the dir of this run",
+        name = "LATEST_SYMLINK",
+        requires = "TIMESTAMP_OUTPUT"
+    )]
+    pub latest_symlink: bool,
 
     #[arg(
         value_parser = timeout_from_millis_str,
@@ -109,84 +1029,1274 @@ struct Opt {
         name = "TIMEOUT",
         default_value = "1000"
     )]
-    timeout: Duration,
+    pub timeout: Duration,
+
+    #[arg(
+        value_parser = timeout_from_millis_str,
+        long,
+        help = "Add the given milliseconds to the timeout for each KiB of the input, for targets whose run time grows with the input length",
+        name = "TIMEOUT_PER_KB"
+    )]
+    pub timeout_per_kb: Option<Duration>,
+
+    #[arg(
+        short = 'x',
+        long,
+        help = "Feed the fuzzer with an user-specified list of tokens (often called \"dictionary\")",
+        name = "TOKENS"
+    )]
+    pub tokens: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Add a single token to the dictionary, can be repeated. Quotes, backslashes and other bytes are escaped like in AFL dictionaries, as \\\", \\\\ and \\xNN",
+        name = "TOKEN"
+    )]
+    pub token: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Disable unicode in the UI (for old terminals)",
+        name = "DISABLE_UNICODE"
+    )]
+    pub disable_unicode: bool,
+
+    #[arg(
+        long,
+        help = "Show the terminal UI instead of printing the stats line by line",
+        name = "TUI"
+    )]
+    pub tui: bool,
+
+    #[arg(
+        long,
+        help = "Append the fuzzing stats to the given file as newline-delimited JSON",
+        name = "STATS_JSON"
+    )]
+    pub stats_json: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Also append the monitor lines to the given file, prefixed with the unix time, e.g. for runs that outlive the terminal",
+        name = "LOG_FILE"
+    )]
+    pub log_file: Option<PathBuf>,
+
+    #[arg(
+        value_parser = duration_from_secs_str,
+        long,
+        help = "Set the interval between two JSON stats records in seconds, default is 10",
+        name = "STATS_INTERVAL",
+        default_value = "10"
+    )]
+    pub stats_interval: Duration,
+
+    #[arg(
+        long,
+        help = "Print the share of the time spent in each stage at every --stats-interval, e.g. to see if the cmplog tracing is worth it",
+        name = "PROFILE_STAGES"
+    )]
+    pub profile_stages: bool,
+
+    #[arg(
+        short = 'g',
+        long,
+        help = "Use GRIMOIRE, a mutator for text-based inputs. Also turns on --generalize, unless it is set explicitly",
+        name = "GRIMOIRE"
+    )]
+    pub grimoire: bool,
+
+    #[arg(
+        value_parser = on_off_from_str,
+        long,
+        help = "Generalize new corpus entries into the gaps and tokens GRIMOIRE mutates (on/off), default is the same as --grimoire. Without generalization, the GRIMOIRE mutators rarely apply",
+        name = "GENERALIZE"
+    )]
+    pub generalize: Option<bool>,
+
+    #[arg(
+        value_parser = duration_from_secs_str,
+        long,
+        help = "Stop fuzzing after the given number of seconds, default is to fuzz forever",
+        name = "MAX_TOTAL_TIME"
+    )]
+    pub max_total_time: Option<Duration>,
+
+    #[arg(
+        value_parser = duration_from_secs_str,
+        long,
+        help = "Stop fuzzing once no new coverage was found for the given number of seconds, by this client or any other one",
+        name = "PLATEAU"
+    )]
+    pub plateau: Option<Duration>,
+
+    #[arg(
+        long,
+        help = "Stop each client after it performed the given number of executions. The limit is per-client, not aggregated across --cores, so that runs are reproducible",
+        name = "MAX_EXECUTIONS"
+    )]
+    pub max_executions: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Minimize the given crashing input instead of fuzzing",
+        name = "TMIN"
+    )]
+    pub tmin: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Set the path of the minimized input, default is <TMIN>.min",
+        name = "TMIN_OUTPUT"
+    )]
+    pub tmin_output: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Set the number of minimization attempts in each round, default is 1024",
+        name = "TMIN_RUNS",
+        default_value = "1024"
+    )]
+    pub tmin_runs: usize,
+
+    #[arg(
+        long,
+        help = "Minimize each new crash right after it was found, with at most --tmin-iters executions. The result goes to the minimized dir next to the crashes dir, named after its own SHA-1, and the crash is kept as it is. Timeouts are not minimized",
+        name = "AUTO_TMIN"
+    )]
+    pub auto_tmin: bool,
+
+    #[arg(
+        long,
+        help = "Set the number of minimization attempts for each crash with --auto-tmin, default is 1024. This bounds how long a client stops fuzzing for each new crash",
+        name = "TMIN_ITERS",
+        default_value = "1024"
+    )]
+    pub tmin_iters: usize,
+
+    #[arg(
+        long,
+        help = "Print the observers and the sizes of their maps at startup, to check that the coverage is collected",
+        name = "DUMP_OBSERVERS"
+    )]
+    pub dump_observers: bool,
+
+    #[arg(
+        long,
+        help = "Seed the RNG of each client with this value XOR its core id, default is to use the current time",
+        name = "SEED"
+    )]
+    pub seed: Option<u64>,
+
+    #[arg(
+        long,
+        help = "Treat an execution holding more than the given heap memory in MB as a crash, 0 to disable, default is 2048. Unlike in libFuzzer, this counts what the execution allocated and did not free yet, not the RSS of the process",
+        name = "RSS_LIMIT_MB",
+        default_value = "2048"
+    )]
+    pub rss_limit_mb: usize,
+
+    #[arg(
+        long,
+        help = "Stop as soon as a solution is found and exit with code 77",
+        name = "EXIT_ON_SOLUTION"
+    )]
+    pub exit_on_solution: bool,
+
+    #[arg(
+        value_parser = on_off_from_str,
+        long,
+        help = "Trace comparisons to solve them with input-to-state replacements, 'on' or 'off', default is on. CmpLog doubles the cost of each execution",
+        name = "CMPLOG",
+        default_value = "on"
+    )]
+    pub cmplog: bool,
+
+    #[arg(
+        long,
+        help = "Skip the input-to-state replacement stage, while still tracing comparisons with --cmplog. To isolate the cost of a stage",
+        name = "NO_I2S"
+    )]
+    pub no_i2s: bool,
+
+    #[arg(
+        long,
+        help = "Skip the havoc, tokens and MOPT mutational stage. To isolate the cost of a stage",
+        name = "NO_POWER"
+    )]
+    pub no_power: bool,
+
+    #[arg(
+        long,
+        help = "Skip the GRIMOIRE mutational stage, also with --grimoire, which then only generalizes the inputs. To isolate the cost of a stage",
+        name = "NO_GRIMOIRE"
+    )]
+    pub no_grimoire: bool,
+
+    #[arg(
+        long,
+        help = "Skip the calibration stage, e.g. to isolate the cost of a stage. Needs --no-power and --scheduler queue, which do not use the calibration data",
+        name = "NO_CALIBRATION",
+        requires = "NO_POWER"
+    )]
+    pub no_calibration: bool,
+
+    #[arg(
+        long,
+        help = "Keep inputs for their coverage only, without timing them, so that two runs with the same --seed on a single core give the same corpus. Implies --scheduler queue and replaces the calibration and power stages with a plain havoc stage, which is slower to find new coverage",
+        name = "DETERMINISTIC"
+    )]
+    pub deterministic: bool,
+
+    #[arg(
+        value_enum,
+        long,
+        help = "Choose the corpus scheduler, default is powerqueue",
+        name = "SCHEDULER",
+        default_value = "powerqueue"
+    )]
+    pub scheduler: SchedulerKind,
+
+    #[arg(
+        value_enum,
+        long,
+        help = "Choose what the scheduler favors among the testcases that cover the same edges: none of them with coverage, the shortest with short or the fastest with fast. Default is the product of length and execution time",
+        name = "FAVOR"
+    )]
+    pub favor: Option<Favor>,
+
+    #[arg(
+        value_parser = power_schedule_from_str,
+        long,
+        help = "Choose the power schedule: explore, fast, coe, lin or quad, default is fast",
+        name = "POWER_SCHEDULE",
+        default_value = "fast"
+    )]
+    pub power_schedule: PowerSchedule,
+
+    #[arg(
+        long,
+        help = "Set how many corpus entries are kept in memory, default is 4096. More entries use more memory but spare disk reads, 0 keeps the corpus on disk only",
+        name = "CORPUS_CACHE",
+        default_value = "4096"
+    )]
+    pub corpus_cache: usize,
+
+    #[arg(
+        long,
+        help = "Set the maximum size of the random initial inputs, used when no --input is given, default is 32",
+        name = "GEN_MAX_SIZE",
+        default_value = "32"
+    )]
+    pub gen_max_size: usize,
+
+    #[arg(
+        long,
+        help = "Set the maximum length of the inputs in bytes, like libFuzzer's -max_len. The mutators do not grow inputs past it, and the target only gets the first bytes of longer ones, e.g. of seeds or GRIMOIRE mutations, which can grow inputs past it",
+        name = "MAX_LEN"
+    )]
+    pub max_len: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Set the number of random initial inputs, used when no --input is given, default is 8. With a dictionary, as many combinations of its tokens are tried too",
+        name = "GEN_COUNT",
+        default_value = "8"
+    )]
+    pub gen_count: usize,
+
+    #[arg(
+        long,
+        help = "Generate printable ASCII initial inputs instead of random bytes, e.g. for parsers of text formats. Used when no --input is given",
+        name = "PRINTABLE"
+    )]
+    pub printable: bool,
+
+    #[arg(
+        long,
+        help = "Run each --input file, and each file inside an --input dir, once and report how the target exited, instead of fuzzing",
+        name = "REPRODUCE"
+    )]
+    pub reproduce: bool,
+
+    #[arg(
+        long,
+        help = "Restart each client with a fresh process after the given number of executions, to bound the state leaking between executions. This is only a mitigation, harnesses should not keep state across runs",
+        name = "RESET_EVERY"
+    )]
+    pub reset_every: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Write the tokens from -x and the ones extracted from the target to a dictionary file on startup",
+        name = "DUMP_TOKENS"
+    )]
+    pub dump_tokens: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Only keep the first crash for each backtrace hash. The hashes are kept in the output directory, so this holds across restarts and runs",
+        name = "UNIQUE_CRASHES"
+    )]
+    pub unique_crashes: bool,
+
+    #[arg(
+        long,
+        help = "Save every crash, instead of only the ones with a new backtrace hash, and capture no backtraces at all. For platforms where unwinding is slow or unreliable",
+        name = "NO_BACKTRACE_DEDUP",
+        conflicts_with = "UNIQUE_CRASHES"
+    )]
+    pub no_backtrace_dedup: bool,
+
+    #[arg(
+        long,
+        help = "Record the corpus entries each corpus entry and solution was mutated from, and write them to a hidden .<solution>.provenance file next to each solution. Best-effort, the mutations are not recorded",
+        name = "TRACE_PROVENANCE"
+    )]
+    pub trace_provenance: bool,
+
+    #[arg(
+        long,
+        help = "Do not save crashes whose backtrace contains the given text, e.g. a function name of a known bug. The client still restarts after such a crash. Can be given several times",
+        name = "IGNORE_CRASH"
+    )]
+    pub ignore_crash: Vec<String>,
+
+    #[arg(
+        value_parser = on_off_from_str,
+        long,
+        help = "Extract tokens from the comparisons in the target binary, 'on' or 'off', default is on",
+        name = "AUTOTOKENS",
+        default_value = "on"
+    )]
+    pub autotokens: bool,
+
+    #[arg(
+        long,
+        help = "Keep at most the given number of tokens, the ones from -x and --token first, default is to keep all",
+        name = "MAX_TOKENS"
+    )]
+    pub max_tokens: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Use the AFL++ output layout, with the queue and crashes dirs of each client in <OUTPUT>/cargo-libafl-<core>, and import the queues of AFL++ output and sync dirs given with --input",
+        name = "AFL_LAYOUT"
+    )]
+    pub afl_layout: bool,
+
+    #[arg(
+        value_parser = stack_pow_from_str,
+        long,
+        help = "Stack up to 2^STACK_POW havoc mutations on each input, between 1 and 10, default is 7",
+        name = "STACK_POW",
+        default_value = "7"
+    )]
+    pub stack_pow: u64,
+
+    #[arg(
+        value_parser = mopt_iterations_from_str,
+        long,
+        help = "Set the number of swarms MOpt evolves the mutation probabilities with, between 1 and 64, default is 5",
+        name = "MOPT_ITERATIONS",
+        default_value = "5"
+    )]
+    pub mopt_iterations: usize,
+
+    #[arg(
+        long,
+        help = "Periodically import the new files another fuzzer writes to this directory",
+        name = "FOREIGN_SYNC_DIR"
+    )]
+    pub foreign_sync_dir: Option<PathBuf>,
+
+    #[arg(
+        value_parser = duration_from_secs_str,
+        long,
+        help = "Set how often to import from --foreign-sync-dir in seconds, default is 60",
+        name = "SYNC_INTERVAL",
+        default_value = "60"
+    )]
+    pub sync_interval: Duration,
+
+    #[arg(
+        value_parser = on_off_from_str,
+        long,
+        help = "Save inputs that time out as solutions, in the timeouts dir next to the crashes dir, 'on' or 'off', default is off",
+        name = "TIMEOUT_IS_CRASH",
+        default_value = "off"
+    )]
+    pub timeout_is_crash: bool,
+
+    #[arg(
+        long,
+        help = "Keep the corpus in memory only. This spares disk accesses, but the corpus is lost on exit, e.g. for short CI runs. Solutions are still written to disk",
+        name = "IN_MEMORY_CORPUS"
+    )]
+    pub in_memory_corpus: bool,
+
+    #[arg(
+        value_enum,
+        long,
+        help = "Choose how the corpus is stored on disk: raw for the plain bytes of the inputs, that other fuzzers can read, or generalized for the serialized inputs with their grimoire generalization. Default is generalized with --grimoire, else raw",
+        name = "CORPUS_FORMAT"
+    )]
+    pub corpus_format: Option<CorpusFormat>,
+
+    #[arg(
+        long,
+        help = "Store the corpus entries gzipped on disk, as <name>.gz files with the plain bytes of the inputs, which are decompressed again when imported with this flag. This saves disk space on long campaigns, at the cost of CPU time for each new entry. Solutions stay uncompressed",
+        name = "COMPRESS_CORPUS",
+        conflicts_with_all = ["CORPUS_FORMAT", "IN_MEMORY_CORPUS"]
+    )]
+    pub compress_corpus: bool,
+
+    #[arg(
+        long,
+        help = "The number of coverage map entries the target is expected to have. Only used to warn at startup if fewer were found, e.g. because parts of the target are not instrumented",
+        name = "MAP_SIZE"
+    )]
+    pub map_size: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Run the harness once on the given file, or on stdin for -, without fuzzing. Exits with 0 if it passed, 77 if it crashed and 70 if it timed out, like libFuzzer",
+        name = "ONE_SHOT",
+        conflicts_with_all = ["REPRODUCE", "TMIN"]
+    )]
+    pub one_shot: Option<PathBuf>,
 
     #[arg(
-        short = 'x',
+        value_parser = duration_from_secs_str,
         long,
-        help = "Feed the fuzzer with an user-specified list of tokens (often called \"dictionary\")",
-        name = "TOKENS"
+        help = "Stop with a diagnostic if a client did not finish a single execution within the given seconds, e.g. because the target hangs on its first input, default is 60. 0 disables the check",
+        name = "STARTUP_TIMEOUT",
+        default_value = "60"
     )]
-    tokens: Vec<PathBuf>,
+    pub startup_timeout: Duration,
 
     #[arg(
+        value_enum,
         long,
-        help = "Disable unicode in the UI (for old terminals)",
-        name = "DISABLE_UNICODE"
+        help = "Choose the shared memory provider: std, mmap or ashmem, default is std. Another one may work in containers where /dev/shm is missing or too small",
+        name = "SHMEM",
+        default_value = "std"
     )]
-    disable_unicode: bool,
+    pub shmem: ShMemKind,
 
     #[arg(
-        short = 'g',
         long,
-        help = "Use GRIMOIRE, a mutator for text-based inputs",
-        name = "GRIMOIRE"
+        help = "Fuzz the entry with this name of a target defined with fuzz_targets!, default is its first entry",
+        name = "ENTRY"
     )]
-    grimoire: bool,
+    pub entry: Option<String>,
 }
 
 extern "C" {
     // We do not actually cross the FFI bound here.
     #[allow(improper_ctypes)]
-    fn rust_fuzzer_test_input(input: &[u8]);
+    fn rust_fuzzer_test_input(input: &[u8]) -> i32;
 
     fn rust_fuzzer_initialize();
 }
 
 static mut BACKTRACE: Option<u64> = None;
 
+/// The heap memory limit for a single execution of the harness in bytes, 0 means no limit
+static MEMORY_LIMIT: AtomicUsize = AtomicUsize::new(0);
+/// The heap memory currently held by the running execution of the harness
+static HARNESS_MEMORY: AtomicIsize = AtomicIsize::new(0);
+/// Set while the harness is running, only its allocations count towards the limit
+static IN_HARNESS: AtomicBool = AtomicBool::new(false);
+/// Set once the harness returned for the first time in this process, see [`spawn_startup_watchdog`]
+static HARNESS_RETURNED: AtomicBool = AtomicBool::new(false);
+/// The length and first bytes of the input the harness runs on until it returned once
+static STARTUP_INPUT: Mutex<Option<(usize, Vec<u8>)>> = Mutex::new(None);
+
+/// Set on `SIGINT` or `SIGTERM` in a client, which then stops fuzzing, see [`install_shutdown_handler`]
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Set once the launcher returned in the main process, see [`spawn_broker_stopper`]
+static LAUNCH_RETURNED: AtomicBool = AtomicBool::new(false);
+
+/// How many bytes of a hanging input are printed by the startup watchdog
+const STARTUP_INPUT_HEAD_LEN: usize = 16;
+
+/// Wraps [`MiMalloc`](mimalloc::MiMalloc) and aborts when the harness holds more heap memory
+/// than [`MEMORY_LIMIT`].
+/// Only the bytes allocated and not yet freed by the running execution count, not the RSS.
+/// The abort is caught by the crash handler, so the input ends up in the crashes dir.
+struct LimitedAllocator;
+
+/// A fixed buffer to format messages into from inside the allocator, where allocating recurses
+struct StackMessage {
+    buf: [u8; 128],
+    len: usize,
+}
+
+impl FmtWrite for StackMessage {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        // Truncate instead of failing, the start of the message is the important part
+        let end = (self.len + s.len()).min(self.buf.len());
+        self.buf[self.len..end].copy_from_slice(&s.as_bytes()[..end - self.len]);
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl LimitedAllocator {
+    #[inline]
+    fn track(&self, delta: isize) {
+        // The fast path without `--rss-limit-mb`, nothing is counted then
+        let limit = MEMORY_LIMIT.load(Ordering::Relaxed);
+        if limit == 0 || !IN_HARNESS.load(Ordering::Relaxed) {
+            return;
+        }
+        let used = HARNESS_MEMORY.fetch_add(delta, Ordering::Relaxed) + delta;
+        if used > 0 && used as usize > limit {
+            // The crash handler allocates, which must not end up here again
+            IN_HARNESS.store(false, Ordering::Relaxed);
+            let mut message = StackMessage {
+                buf: [0; 128],
+                len: 0,
+            };
+            let _ = writeln!(
+                message,
+                "==ERROR: out-of-memory (used: {}Mb; exceeds: {}Mb)",
+                used as usize >> 20,
+                limit >> 20
+            );
+            unsafe {
+                libc::write(
+                    libc::STDERR_FILENO,
+                    message.buf.as_ptr().cast(),
+                    message.len,
+                );
+            }
+            // Called before entering MiMalloc, so the crash handler finds it in a consistent state
+            std::process::abort();
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for LimitedAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.track(layout.size() as isize);
+        mimalloc::MiMalloc.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.track(layout.size() as isize);
+        mimalloc::MiMalloc.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.track(-(layout.size() as isize));
+        mimalloc::MiMalloc.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.track(new_size as isize - layout.size() as isize);
+        mimalloc::MiMalloc.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Runs the harness on `buf`, tracking its memory usage.
+/// Returns `false` if the target rejected the input from the corpus.
+fn run_harness(buf: &[u8]) -> bool {
+    if !HARNESS_RETURNED.load(Ordering::Relaxed) {
+        let head = buf[..buf.len().min(STARTUP_INPUT_HEAD_LEN)].to_vec();
+        *STARTUP_INPUT.lock().unwrap() = Some((buf.len(), head));
+    }
+    HARNESS_MEMORY.store(0, Ordering::Relaxed);
+    IN_HARNESS.store(true, Ordering::Relaxed);
+    let ret = unsafe { rust_fuzzer_test_input(buf) };
+    IN_HARNESS.store(false, Ordering::Relaxed);
+    HARNESS_RETURNED.store(true, Ordering::Relaxed);
+    ret != -1
+}
+
+/// The signal handler installed by [`install_shutdown_handler`], sets [`SHUTDOWN_REQUESTED`]
+/// so that the fuzzing loop stops after the current iteration.
+extern "C" fn request_shutdown(_signal: libc::c_int) {
+    // A second signal does not wait for the current iteration anymore
+    if SHUTDOWN_REQUESTED.swap(true, Ordering::Relaxed) {
+        unsafe { libc::_exit(130) };
+    }
+}
+
+/// Lets a client finish its current fuzzing iteration on `SIGINT` or `SIGTERM`, instead of
+/// being killed while writing a testcase. Ctrl-C sends it to all processes, and the launcher
+/// sends `SIGINT` to the clients once the broker exited.
+fn install_shutdown_handler() {
+    let handler = request_shutdown as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+/// Stops the fuzzer if the harness did not return once within `timeout` in this client,
+/// instead of leaving the user in front of a frozen status screen.
+/// The diagnostic is left in `hang_file` for the main process, which is stopped with `SIGINT`
+/// like on Ctrl-C, and then fails with it.
+/// `respawner` is the verified respawner of this client, see [`verified_respawner`], which is
+/// killed so that it does not restart the client. Without one, no other process is killed.
+fn spawn_startup_watchdog(
+    timeout: Duration,
+    hang_file: PathBuf,
+    main_pid: u32,
+    respawner: Option<u32>,
+) {
+    thread::spawn(move || {
+        thread::sleep(timeout);
+        if HARNESS_RETURNED.load(Ordering::Relaxed) {
+            return;
+        }
+        let diagnostic = match &*STARTUP_INPUT.lock().unwrap() {
+            Some((len, head)) => format!(
+                "No execution finished within {}s, the target appears to hang on an input of length {} starting with {:?}, run with --reproduce on it to debug",
+                timeout.as_secs(),
+                len,
+                head
+            ),
+            None => format!(
+                "No execution finished within {}s, the target appears to hang before running the first input",
+                timeout.as_secs()
+            ),
+        };
+        if let Err(e) = fs::write(&hang_file, &diagnostic) {
+            eprintln!("{}", diagnostic);
+            eprintln!("Failed to write {:?}: {}", &hang_file, e);
+        }
+        unsafe {
+            libc::kill(main_pid as libc::pid_t, libc::SIGINT);
+        }
+        // Stop the respawner too, so that it does not restart this client. It is still the
+        // parent of this client if its pid was not reused by another process.
+        if let Some(respawner) = respawner {
+            if std::os::unix::process::parent_id() == respawner {
+                unsafe {
+                    libc::kill(respawner as libc::pid_t, libc::SIGKILL);
+                }
+            }
+        }
+        std::process::exit(1);
+    });
+}
+
+/// The parent of this client, if it is a respawner forked by the launcher in the main process
+/// `main_pid`, and not e.g. an init process that adopted the client after its respawner died
+fn verified_respawner(main_pid: u32) -> Option<u32> {
+    let parent = std::os::unix::process::parent_id();
+    (parent != main_pid && parent_of(parent) == Some(main_pid)).then_some(parent)
+}
+
+/// The parent pid of the process `pid`, or `None` if it cannot be found
+#[cfg(target_os = "linux")]
+fn parent_of(pid: u32) -> Option<u32> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name in parentheses may contain spaces, the state and parent pid follow it
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// The parent pid of the process `pid`, or `None` if it cannot be found
+#[cfg(not(target_os = "linux"))]
+fn parent_of(pid: u32) -> Option<u32> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "ppid=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Stops the broker once the respawners of the `clients` all exited, e.g. on
+/// `--max-total-time`. The broker keeps serving until it gets `SIGINT`, which makes it return
+/// from `launch` like on Ctrl-C.
+/// Each client registers the pid of its respawner in `respawners_dir`, and only the ones that
+/// are children of this process are waited for, so that no other child is reaped here.
+fn spawn_broker_stopper(clients: usize, respawners_dir: PathBuf) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut exited = HashSet::new();
+        // Polling, so that this stops waiting once the launcher returned
+        while !LAUNCH_RETURNED.load(Ordering::Relaxed) {
+            let registered = fs::read_dir(&respawners_dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse::<libc::pid_t>().ok());
+            for pid in registered {
+                let mut status = 0;
+                // Fails for the pids that are not children of this process
+                if !exited.contains(&pid)
+                    && unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } == pid
+                {
+                    exited.insert(pid);
+                }
+            }
+            if exited.len() >= clients {
+                unsafe { libc::kill(std::process::id() as libc::pid_t, libc::SIGINT) };
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    })
+}
+
+/// Runs the harness once on each file in `inputs`, or in the dirs in `inputs`, and prints how it exited.
+/// A file named `-` is read from stdin. Returns the inputs that crashed or timed out.
+fn reproduce(inputs: &[PathBuf], timeout: Duration) -> Result<Vec<(PathBuf, ExitKind)>, Error> {
+    let mut mgr = SimpleEventManager::new(SimpleMonitor::new(print_monitor_line as fn(String)));
+    let mut shmem_provider = StdShMemProvider::new()?;
+
+    // The backtrace hash is computed in the forked child, so it has to live in shared memory
+    let mut backtrace = shmem_provider.new_shmem_object::<Option<u64>>()?;
+    let backtrace_observer = BacktraceObserver::new(
+        "BacktraceObserver",
+        unsafe { backtrace.as_object_mut::<Option<u64>>() },
+        HarnessType::Child,
+    );
+
+    // The harness is run in a forked child, so that a crash does not stop us from running the next input
+    let mut harness = |input: &BytesInput| {
+        let target = input.target_bytes();
+        run_harness(target.as_slice());
+        ExitKind::Ok
+    };
+
+    let mut state = StdState::new(
+        StdRand::with_seed(current_nanos()),
+        InMemoryCorpus::new(),
+        InMemoryCorpus::new(),
+        &mut (),
+        &mut (),
+    )?;
+    let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), (), ());
+
+    let mut executor = TimeoutInProcessForkExecutor::new(
+        &mut harness,
+        tuple_list!(backtrace_observer),
+        &mut fuzzer,
+        &mut state,
+        &mut mgr,
+        timeout,
+        shmem_provider,
+    )?;
+
+    let mut files = vec![];
+    for input in inputs {
+        if input.is_dir() {
+            for entry in fs::read_dir(input)? {
+                let path = entry?.path();
+                if path.is_file() {
+                    files.push(path);
+                }
+            }
+        } else {
+            files.push(input.clone());
+        }
+    }
+
+    let total = files.len();
+    let mut failures = vec![];
+    for file in files {
+        let input = if file.as_os_str() == "-" {
+            let mut bytes = vec![];
+            std::io::stdin().read_to_end(&mut bytes)?;
+            BytesInput::new(bytes)
+        } else {
+            BytesInput::from_file(&file)?
+        };
+        let exit_kind = fuzzer.execute_input(&mut state, &mut executor, &mut mgr, &input)?;
+        println!("{:?}: {:?}", &file, exit_kind);
+        match exit_kind {
+            ExitKind::Crash => {
+                if let Some(hash) = unsafe { *backtrace.as_object::<Option<u64>>() } {
+                    println!("Backtrace hash: {:016x}", hash);
+                }
+                failures.push((file, exit_kind));
+            }
+            ExitKind::Timeout => failures.push((file, exit_kind)),
+            _ => (),
+        }
+    }
+
+    if !failures.is_empty() {
+        println!("{} of {} inputs failed:", failures.len(), total);
+        for (file, exit_kind) in &failures {
+            println!("  {:?}: {:?}", file, exit_kind);
+        }
+    }
+    Ok(failures)
+}
+
+/// Runs the harness once on `input`, or on stdin for `-`, without starting the fuzzer.
+/// This is how external orchestrators like `ClusterFuzz` run a target, one input per process.
+/// The target has to be initialized already, like [`run`] does before calling this.
+pub fn one_shot(input: &Path, timeout: Duration) -> Result<ExitKind, Error> {
+    let failures = reproduce(&[input.to_path_buf()], timeout)?;
+    Ok(failures
+        .into_iter()
+        .next()
+        .map_or(ExitKind::Ok, |(_, exit_kind)| exit_kind))
+}
+
+/// Shrinks the crashing `input` until no further reduction is possible, or for at most
+/// `max_rounds`, and writes it to `output`
+fn minimize_crash(
+    input_path: &Path,
+    output: &Path,
+    timeout: Duration,
+    runs: usize,
+    max_rounds: usize,
+) -> Result<(), Error> {
+    let mut mgr = SimpleEventManager::new(SimpleMonitor::new(|s| println!("{}", s)));
+
+    // The harness is run in a forked child, so that crashes do not take us down
+    let mut harness = |input: &BytesInput| {
+        let target = input.target_bytes();
+        run_harness(target.as_slice());
+        ExitKind::Ok
+    };
+
+    let mut state = StdState::new(
+        StdRand::with_seed(current_nanos()),
+        InMemoryCorpus::new(),
+        InMemoryCorpus::new(),
+        &mut (),
+        &mut (),
+    )?;
+    let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), (), ());
+
+    let mut executor = TimeoutInProcessForkExecutor::new(
+        &mut harness,
+        (),
+        &mut fuzzer,
+        &mut state,
+        &mut mgr,
+        timeout,
+        StdShMemProvider::new()?,
+    )?;
+
+    let input = BytesInput::from_file(input_path)?;
+    if fuzzer.execute_input(&mut state, &mut executor, &mut mgr, &input)? != ExitKind::Crash {
+        return Err(Error::illegal_argument(
+            "The input does not crash the target, nothing to minimize",
+        ));
+    }
+
+    let original_len = input.len();
+    let idx = state.corpus_mut().add(Testcase::new(input))?;
+
+    let tmin = StdTMinMutationalStage::new(
+        StdScheduledMutator::new(havoc_mutations()),
+        CrashFeedbackFactory::default(),
+        runs,
+    );
+    let mut stages = tuple_list!(tmin);
+
+    // Each round replaces the testcase with a smaller crashing one, stop once it no longer shrinks
+    let mut len = original_len;
+    for _ in 0..max_rounds {
+        fuzzer.fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr)?;
+        let new_len = state.corpus().get(idx)?.borrow_mut().load_input()?.len();
+        if new_len >= len {
+            break;
+        }
+        len = new_len;
+    }
+
+    let minimized = state.corpus().get(idx)?.borrow_mut().load_input()?.clone();
+    fs::write(output, minimized.bytes())?;
+    println!(
+        "Minimized {:?} from {} to {} bytes, saved in {:?}",
+        input_path, original_len, len, output
+    );
+    Ok(())
+}
+
+/// Minimizes the new `crash` with at most `iters` executions for `--auto-tmin`, and saves the
+/// result to `minimized_dir` as `crash-<sha1>` after its own bytes. The crash and its metadata
+/// files stay as they are.
+/// Runs in a forked child, so that the fork executor does not replace the crash handlers of
+/// the client that calls this.
+fn auto_tmin(crash: &Path, minimized_dir: &Path, timeout: Duration, iters: usize) {
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        eprintln!("Failed to fork to minimize {:?}", crash);
+        return;
+    }
+    if pid > 0 {
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        return;
+    }
+
+    let minimize = || -> Result<(), Error> {
+        fs::create_dir_all(minimized_dir)?;
+        // Hidden until it is complete, so that it is not mistaken for a solution
+        let name = crash
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+        let partial = minimized_dir.join(format!(".{}.partial", name));
+        minimize_crash(crash, &partial, timeout, iters, 1)?;
+        let sha1 = sha1_smol::Sha1::from(fs::read(&partial)?).digest();
+        fs::rename(&partial, minimized_dir.join(format!("crash-{}", sha1)))?;
+        Ok(())
+    };
+    let code = match minimize() {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Failed to minimize {:?}: {}", crash, e);
+            1
+        }
+    };
+    // Skip the destructors and exit handlers of the client, this is a copy of it
+    unsafe { libc::_exit(code) };
+}
+
+impl Default for Opt {
+    /// The options as if none were given on the commandline
+    fn default() -> Self {
+        Opt::parse_from(["cargo-libafl"])
+    }
+}
+
+/// Passes the entry picked with `--entry` on to the harness, see `fuzz_targets!`.
+/// The forked clients inherit it.
+fn select_entry(entry: Option<&str>) {
+    if let Some(entry) = entry {
+        env::set_var("CARGO_LIBAFL_ENTRY", entry);
+    }
+}
+
 /// The main fn, `no_mangle` as it is a C symbol
-#[allow(clippy::too_many_lines)]
-#[no_mangle]
+#[cfg_attr(not(test), no_mangle)]
 pub fn main() {
+    let opt = Opt::parse();
+    let reproduce = opt.reproduce;
+    match run(opt) {
+        Ok(0) => (),
+        Ok(_) if reproduce => std::process::exit(1),
+        Ok(_) => std::process::exit(SOLUTION_EXIT_CODE),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the fuzzer, or one of the other modes, with the given options.
+/// Returns the number of new solutions, or the number of failing inputs with `reproduce`.
+/// With `one_shot`, this exits the process with the exit code of libFuzzer instead.
+#[allow(clippy::too_many_lines)]
+pub fn run(mut opt: Opt) -> Result<usize, Error> {
+    select_entry(opt.entry.as_deref());
     unsafe {
         rust_fuzzer_initialize();
     }
 
-    let workdir = env::current_dir().unwrap();
+    let workdir = env::current_dir()?;
 
-    let opt = Opt::parse();
+    MEMORY_LIMIT.store(opt.rss_limit_mb << 20, Ordering::Relaxed);
 
-    let cores = opt.cores;
-    let broker_port = opt.broker_port.unwrap_or_else(|| {
-        let port = portpicker::pick_unused_port().expect("No ports free");
-        println!("Picking the free port {}", port);
-        port
-    });
+    if let Some(input) = &opt.one_shot {
+        // Mirror the exit codes of libFuzzer, which triage tools expect
+        std::process::exit(match one_shot(input, opt.timeout) {
+            Ok(ExitKind::Ok) => 0,
+            Ok(ExitKind::Timeout) => LIBFUZZER_TIMEOUT_EXIT_CODE,
+            Ok(_) => LIBFUZZER_CRASH_EXIT_CODE,
+            Err(err) => {
+                eprintln!("{}", err);
+                1
+            }
+        });
+    }
+
+    if let Some(input) = &opt.tmin {
+        let output = opt.tmin_output.clone().unwrap_or_else(|| {
+            let mut output = input.clone().into_os_string();
+            output.push(".min");
+            PathBuf::from(output)
+        });
+        minimize_crash(input, &output, opt.timeout, opt.tmin_runs, usize::MAX)?;
+        return Ok(0);
+    }
+
+    // Removes the unpacked seeds again when dropped at the end of the run
+    let _extracted_archives = ExtractedArchives::extract(&mut opt.input)?;
+
+    if opt.reproduce {
+        if opt.input.is_empty() {
+            return Err(Error::illegal_argument(
+                "--reproduce needs at least one --input to run!",
+            ));
+        }
+        // Let the panic handler of the crashing child print the full backtrace
+        if env::var_os("RUST_BACKTRACE").is_none() {
+            env::set_var("RUST_BACKTRACE", "1");
+        }
+        return Ok(reproduce(&opt.input, opt.timeout)?.len());
+    }
+
+    let mut cores = match opt.workers {
+        Some(workers) => match workers_to_cores(workers) {
+            Ok(cores) => {
+                println!("Running {} workers on the cores {:?}", workers, cores.ids);
+                cores
+            }
+            Err(err) => {
+                return Err(Error::illegal_argument(format!(
+                    "Cannot run {} workers: {:?}",
+                    workers, err
+                )));
+            }
+        },
+        None => opt.cores,
+    };
+    let import_only = opt.import_only;
+    if import_only {
+        // A single client, so that the seeds are run once and in order
+        cores.ids.truncate(1);
+    }
+    // Only the first client replays them, the others get the new entries from it
+    let first_core: usize = cores.ids.first().copied().map_or(0, Into::into);
+    let mut solution_files = opt
+        .import_solutions
+        .as_deref()
+        .map_or_else(Vec::new, seed_files);
+    // The same order in every restarted client, for `ImportedSolutionsMetadata`
+    solution_files.sort();
+    let broker_port = match opt.broker_port {
+        Some(port) => port,
+        None => {
+            let port = portpicker::pick_unused_port()
+                .ok_or_else(|| Error::illegal_state("No ports free"))?;
+            println!("Picking the free port {}", port);
+            port
+        }
+    };
     let remote_broker_addr = opt.remote_broker_addr;
-    let input_dirs = opt.input;
-    let output_dir = opt.output;
+    // Clients with the same configuration can run the inputs of each other without re-executing
+    let configuration = opt
+        .config_name
+        .as_deref()
+        .map_or_else(EventConfig::from_build_id, EventConfig::from_name);
+    // Directories are imported as a whole, single files (e.g. artifacts) one by one
+    let (mut input_dirs, mut input_files): (Vec<PathBuf>, Vec<PathBuf>) =
+        opt.input.into_iter().partition(|path| path.is_dir());
+    let afl_layout = opt.afl_layout;
+    if afl_layout {
+        input_dirs = input_dirs.into_iter().flat_map(expand_afl_queues).collect();
+    }
+    let compress_corpus = opt.compress_corpus;
+    if compress_corpus || import_only {
+        // LibAFL would import the compressed entries as they are, so read them one by one
+        input_files.extend(input_dirs.drain(..).flat_map(|dir| seed_files(&dir)));
+    }
+    if import_only {
+        // The smaller seeds claim the coverage first, so the larger ones only stay for new edges
+        input_files.sort_by_cached_key(|file| fs::metadata(file).map_or(0, |meta| meta.len()));
+    }
+    let mut output_dir = opt.output;
+    if opt.timestamp_output {
+        let run_dir = format!("{}-{}", target_name(), rfc3339_now());
+        fs::create_dir_all(&output_dir)?;
+        if opt.latest_symlink {
+            let latest = output_dir.join("latest");
+            if latest.is_symlink() {
+                fs::remove_file(&latest)?;
+            }
+            // Relative, so that the output directory can be moved
+            std::os::unix::fs::symlink(&run_dir, &latest)?;
+        }
+        output_dir = output_dir.join(run_dir);
+    }
     let token_files = opt.tokens;
+    let inline_tokens = opt
+        .token
+        .iter()
+        .map(|token| unescape_token(token))
+        .collect::<Result<Vec<_>, _>>()?;
+    let use_autotokens = opt.autotokens;
+    let max_tokens = opt.max_tokens;
     let timeout_ms = opt.timeout;
-    // let cmplog_enabled = matches.is_present("cmplog");
+    let timeout_per_kb = opt.timeout_per_kb;
+    // Computed once before launching, so that every client observes the same deadline
+    let start_time = current_time();
+    let deadline = opt
+        .max_total_time
+        .map(|max_total_time| start_time + max_total_time);
+    let stop_condition = StopCondition::new(
+        deadline,
+        opt.plateau,
+        opt.max_executions,
+        opt.exit_on_solution,
+        opt.reset_every,
+    );
+    let seed = opt.seed;
+    let cmplog_enabled = opt.cmplog;
+    let grimoire_enabled = opt.grimoire && !opt.no_grimoire;
+    let i2s_enabled = opt.cmplog && !opt.no_i2s;
+    // The calibration, the power schedules and the time feedback all depend on execution times
+    let deterministic = opt.deterministic;
+    let power_enabled = !opt.no_power && !deterministic;
+    let havoc_enabled = !opt.no_power && deterministic;
+    let calibration_enabled = !opt.no_calibration && !deterministic;
+    let generalize_enabled = opt.generalize.unwrap_or(opt.grimoire);
+    let scheduler_kind = if deterministic {
+        SchedulerKind::Queue
+    } else {
+        opt.scheduler
+    };
+    if opt.no_calibration && scheduler_kind != SchedulerKind::Queue {
+        return Err(Error::illegal_argument(
+            "--no-calibration needs --scheduler queue, the other schedulers use the calibration data!",
+        ));
+    }
+    let power_schedule = opt.power_schedule;
+    let favor = opt.favor;
+    let corpus_cache = opt.corpus_cache;
+    let max_len = opt.max_len.map(|max_len| max_len.max(1));
+    let gen_max_size = max_len.map_or(opt.gen_max_size, |max_len| opt.gen_max_size.min(max_len));
+    let gen_count = opt.gen_count;
+    let printable = opt.printable;
+    let stack_pow = opt.stack_pow;
+    let foreign_sync_dir = opt.foreign_sync_dir;
+    let sync_interval = opt.sync_interval;
+    let timeout_is_crash = opt.timeout_is_crash;
+    let in_memory_corpus = opt.in_memory_corpus;
+    let corpus_format = opt
+        .corpus_format
+        .unwrap_or(if opt.grimoire && !opt.compress_corpus {
+            CorpusFormat::Generalized
+        } else {
+            CorpusFormat::Raw
+        });
+    let mopt_iterations = opt.mopt_iterations;
 
     if fs::create_dir(&output_dir).is_err() {
         println!("Out dir at {:?} already exists.", &output_dir);
         if !output_dir.is_dir() {
-            eprintln!("Out dir at {:?} is not a valid directory!", &output_dir);
-            return;
+            return Err(Error::illegal_argument(format!(
+                "Out dir at {:?} is not a valid directory!",
+                &output_dir
+            )));
         }
     }
+    // Without the AFL++ layout, all clients share these dirs
     let crashes_dir = output_dir.join("crashes");
+    let timeouts_dir = output_dir.join("timeouts");
     let corpus_dir = output_dir.join("corpus");
+    let crash_hashes_file = opt.unique_crashes.then(|| output_dir.join(".crash_hashes"));
+    let ignore_crash = opt.ignore_crash;
+    let backtrace_dedup = !opt.no_backtrace_dedup;
+    let trace_provenance = opt.trace_provenance;
+    // Written by the startup watchdog of a client
+    let startup_hang_file = output_dir.join(".startup_hang");
+    let _ = fs::remove_file(&startup_hang_file);
+    let startup_timeout = opt.startup_timeout;
+    // Written by the first client that stops on a coverage plateau
+    let plateau_file = output_dir.join(".plateau");
+    let _ = fs::remove_file(&plateau_file);
+    // The process that runs the broker, the launcher forks the clients from it
+    let main_pid = std::process::id();
+    // Where the clients register their respawners, see `spawn_broker_stopper`
+    let respawners_dir = output_dir.join(".respawners");
+    let _ = fs::remove_dir_all(&respawners_dir);
+    fs::create_dir_all(&respawners_dir)?;
+
+    if let Some(dump_path) = &opt.dump_tokens {
+        load_tokens(&token_files, &inline_tokens, use_autotokens, max_tokens)
+            .and_then(|toks| write_dictionary(&toks, dump_path))?;
+        println!("Dumped the tokens to {:?}", dump_path);
+    }
 
     println!("Workdir: {:?}", workdir.to_string_lossy().to_string());
 
-    let shmem_provider = StdShMemProvider::new().expect("Failed to init shared memory");
+    report_coverage_maps(opt.map_size);
+    if opt.dump_observers {
+        dump_observers(opt.cmplog, !opt.no_backtrace_dedup);
+    }
+
+    let count_all_solutions = || {
+        solutions_dirs(&output_dir, afl_layout)
+            .iter()
+            .map(|dir| count_solutions(dir))
+            .sum::<usize>()
+    };
+    let solutions_before = count_all_solutions();
+    let auto_tmin = opt.auto_tmin;
+    let tmin_iters = opt.tmin_iters;
+
+    let shmem_provider = shmem_provider(opt.shmem)?;
+
+    let monitor = if opt.tui {
+        #[cfg(feature = "tui")]
+        {
+            let target_name = target_name();
+            RuntimeMonitor::Tui(TuiMonitor::new(
+                format!(
+                    "cargo-libafl v{} - {} on {} cores",
+                    VERSION,
+                    target_name,
+                    cores.ids.len()
+                ),
+                !opt.disable_unicode,
+            ))
+        }
+        #[cfg(not(feature = "tui"))]
+        {
+            return Err(Error::illegal_argument(
+                "--tui requires the runtime to be built with the `tui` feature!",
+            ));
+        }
+    } else {
+        RuntimeMonitor::Simple(SimpleMonitor::new(print_monitor_line as fn(String)))
+    };
+    let summary = Rc::new(RefCell::new(None));
+    let monitor = LogFileMonitor::new(monitor, opt.log_file);
+    let monitor =
+        JsonStatsMonitor::new(monitor, opt.stats_json, opt.stats_interval, summary.clone());
+    // The terminal UI lists the stage times of each client itself
+    let profile_stages = opt.profile_stages;
+    let monitor = StageTimesMonitor::new(monitor, profile_stages && !opt.tui, opt.stats_interval);
+
+    let mut run_client = |state: Option<StdState<_, _, _, _>>, mut mgr, core_id| {
+        let core_id: usize = core_id.into();
+
+        install_shutdown_handler();
+        let _ = fs::write(
+            respawners_dir.join(std::os::unix::process::parent_id().to_string()),
+            "",
+        );
+        if !startup_timeout.is_zero() {
+            spawn_startup_watchdog(
+                startup_timeout,
+                startup_hang_file.clone(),
+                main_pid,
+                verified_respawner(main_pid),
+            );
+        }
 
-    let monitor = TuiMonitor::new(format!("cargo-libafl v{}", VERSION), !opt.disable_unicode);
+        // AFL++ keeps a `queue` and `crashes` dir for each instance
+        let (corpus_dir, crashes_dir, timeouts_dir) = if afl_layout {
+            let instance_dir = output_dir.join(format!("cargo-libafl-{}", core_id));
+            (
+                instance_dir.join("queue"),
+                instance_dir.join("crashes"),
+                instance_dir.join("timeouts"),
+            )
+        } else {
+            (
+                corpus_dir.clone(),
+                crashes_dir.clone(),
+                timeouts_dir.clone(),
+            )
+        };
+        let minimized_dir = crashes_dir.with_file_name("minimized");
 
-    let mut run_client = |state: Option<StdState<_, _, _, _>>, mut mgr, _core_id| {
         // Create an observation channel using the coverage map
         let edges = unsafe { &mut COUNTERS_MAPS };
         let edges_observer =
@@ -199,42 +2309,102 @@ pub fn main() {
         let cmplog = unsafe { &mut CMPLOG_MAP };
         let cmplog_observer = CmpLogObserver::new("cmplog", cmplog, true);
 
-        // Create a stacktrace observer
+        // Create a stacktrace observer. Without deduplication it stays in the observers for
+        // their type, but as the harness of a child process it never unwinds in this one
         let backtrace_observer = BacktraceObserver::new(
             "BacktraceObserver",
             unsafe { &mut BACKTRACE },
-            libafl::observers::HarnessType::InProcess,
+            if backtrace_dedup {
+                HarnessType::InProcess
+            } else {
+                HarnessType::Child
+            },
         );
 
         // New maximization map feedback linked to the edges observer
         let map_feedback = MaxMapFeedback::new_tracking(&edges_observer, true, false);
 
         let calibration = CalibrationStage::new(&map_feedback);
+        let skippable_calibration =
+            SkippableStage::new(calibration, |_s| calibration_enabled.into());
 
         // Feedback to rate the interestingness of an input
         // This one is composed by two Feedbacks in OR
         let mut feedback = feedback_or!(
             map_feedback,
-            // Time feedback, this one does not need a feedback state
-            TimeFeedback::new_with_observer(&time_observer)
+            // Time feedback, this one does not need a feedback state, off with --deterministic
+            feedback_and_fast!(
+                ConstFeedback::new(!deterministic),
+                TimeFeedback::new_with_observer(&time_observer)
+            ),
+            ProvenanceFeedback {
+                enabled: trace_provenance
+            }
         );
 
         // A feedback to choose if an input is a solution or not
+        // Timeouts have no backtrace hash, so they skip the deduplication of crashes
         let mut objective = feedback_and_fast!(
-            CrashFeedback::new(),
-            NewHashFeedback::new(&backtrace_observer)
+            feedback_or_fast!(
+                feedback_and_fast!(
+                    CrashFeedback::new(),
+                    // Known crashes go first, so that their hashes are not recorded
+                    IgnoreCrashFeedback {
+                        patterns: ignore_crash.clone(),
+                    },
+                    feedback_or_fast!(
+                        ConstFeedback::new(!backtrace_dedup),
+                        NewHashFeedback::new(&backtrace_observer)
+                    ),
+                    UniqueCrashFeedback {
+                        hashes_file: crash_hashes_file.clone(),
+                    }
+                ),
+                feedback_and_fast!(ConstFeedback::new(timeout_is_crash), TimeoutFeedback::new())
+            ),
+            feedback_or!(
+                CrashMetadataFeedback {
+                    skip_backtrace: !backtrace_dedup,
+                    ..CrashMetadataFeedback::default()
+                },
+                ProvenanceFeedback {
+                    enabled: trace_provenance
+                }
+            )
         );
 
         // If not restarting, create a State from scratch
         let mut state = state.unwrap_or_else(|| {
             StdState::new(
                 // RNG
-                StdRand::with_seed(current_nanos()),
-                // Corpus that will be evolved, we keep it in memory for performance
-                CachedOnDiskCorpus::new(corpus_dir.clone(), 4096).unwrap(),
-                // Corpus in which we store solutions (crashes in this example),
-                // on disk so the user can get them after stopping the fuzzer
-                OnDiskCorpus::new(crashes_dir.clone()).unwrap(),
+                StdRand::with_seed(seed.map_or_else(current_nanos, |seed| seed ^ core_id as u64)),
+                // Corpus that will be evolved, we keep part of it in memory for performance,
+                // or all of it with --in-memory-corpus
+                if in_memory_corpus {
+                    RuntimeCorpus::InMemory(InMemoryCorpus::new())
+                } else {
+                    match corpus_format {
+                        CorpusFormat::Raw => RuntimeCorpus::Raw(
+                            RawOnDiskCorpus::new(corpus_dir, corpus_cache, compress_corpus)
+                                .unwrap(),
+                        ),
+                        CorpusFormat::Generalized if corpus_cache == 0 => {
+                            RuntimeCorpus::OnDisk(OnDiskCorpus::new(corpus_dir).unwrap())
+                        }
+                        CorpusFormat::Generalized => RuntimeCorpus::Cached(
+                            CachedOnDiskCorpus::new(corpus_dir, corpus_cache).unwrap(),
+                        ),
+                    }
+                },
+                // Corpus in which we store solutions, on disk so the user can get them
+                // after stopping the fuzzer, together with their metadata for triage.
+                // Timeouts go to their own dir, to keep them apart from the crashes
+                SolutionsCorpus::new(
+                    crashes_dir,
+                    timeout_is_crash.then(|| timeouts_dir),
+                    auto_tmin,
+                )
+                .unwrap(),
                 // A reference to the feedbacks, to create their feedback state
                 &mut feedback,
                 // A reference to the objectives, to create their objective state
@@ -243,17 +2413,14 @@ pub fn main() {
             .expect("Failed to create state")
         });
 
+        // The havoc and token mutators do not grow inputs past the max size
+        if let Some(max_len) = max_len {
+            state.set_max_size(max_len);
+        }
+
         // Read tokens
         if state.metadata().get::<Tokens>().is_none() {
-            let mut toks = Tokens::default();
-            for tokenfile in &token_files {
-                toks.add_from_file(tokenfile)?;
-            }
-            #[cfg(any(target_os = "linux", target_vendor = "apple"))]
-            {
-                toks += autotokens()?;
-            }
-
+            let toks = load_tokens(&token_files, &inline_tokens, use_autotokens, max_tokens)?;
             if !toks.is_empty() {
                 state.add_metadata(toks);
             }
@@ -262,13 +2429,14 @@ pub fn main() {
         // Setup a randomic Input2State stage
         let i2s =
             StdMutationalStage::new(StdScheduledMutator::new(tuple_list!(I2SRandReplace::new())));
+        let skippable_i2s = SkippableStage::new(i2s, |_s| i2s_enabled.into());
 
         // Setup a MOPT mutator
         let mutator = StdMOptMutator::new(
             &mut state,
             havoc_mutations().merge(tokens_mutations()),
-            7,
-            5,
+            stack_pow,
+            mopt_iterations,
         )?;
 
         let grimoire_mutator = StdScheduledMutator::with_max_stack_pow(
@@ -283,13 +2451,28 @@ pub fn main() {
             3,
         );
         let grimoire = StdMutationalStage::new(grimoire_mutator);
-        let skippable_grimoire = SkippableStage::new(grimoire, |_s| opt.grimoire.into());
+        let skippable_grimoire = SkippableStage::new(grimoire, |_s| grimoire_enabled.into());
 
         let power = StdPowerMutationalStage::new(mutator, &edges_observer);
+        let skippable_power = SkippableStage::new(power, |_s| power_enabled.into());
+
+        // Replaces the power stage with --deterministic, its iterations only depend on the RNG
+        let havoc = StdMutationalStage::new(StdScheduledMutator::with_max_stack_pow(
+            havoc_mutations().merge(tokens_mutations()),
+            stack_pow,
+        ));
+        let skippable_havoc = SkippableStage::new(havoc, |_s| havoc_enabled.into());
+
+        // The power mutational stage needs the scheduler metadata, that the plain queue does not add
+        if scheduler_kind == SchedulerKind::Queue
+            && state.metadata().get::<SchedulerMetadata>().is_none()
+        {
+            state.add_metadata(SchedulerMetadata::new(Some(power_schedule)));
+        }
 
         // A minimization+queue policy to get testcasess from the corpus
         let scheduler =
-            IndexesLenTimeMinimizerScheduler::new(PowerQueueScheduler::new(PowerSchedule::FAST));
+            FavorScheduler::new(favor, RuntimeScheduler::new(scheduler_kind, power_schedule));
 
         // A fuzzer with feedbacks and a corpus scheduler
         let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
@@ -297,9 +2480,15 @@ pub fn main() {
         // The wrapped harness function, calling out to the LLVM-style harness
         let mut harness = |input: &GeneralizedInput| {
             let target = input.target_bytes();
-            let buf = target.as_slice();
-            unsafe {
-                rust_fuzzer_test_input(buf);
+            let mut bytes = target.as_slice();
+            if let Some(max_len) = max_len {
+                bytes = &bytes[..bytes.len().min(max_len)];
+            }
+            if !run_harness(bytes) {
+                // Without coverage the feedbacks will never add a rejected input to the corpus
+                for map in unsafe { COUNTERS_MAPS.iter_mut() } {
+                    map.fill(0);
+                }
             }
             ExitKind::Ok
         };
@@ -309,19 +2498,23 @@ pub fn main() {
         let generalization = GeneralizationStage::new(&edges_observer);
 
         let skippable_generalization =
-            SkippableStage::new(generalization, |_s| opt.grimoire.into());
+            SkippableStage::new(generalization, |_s| generalize_enabled.into());
 
         // Create the executor for an in-process function with one observer for edge coverage and one for the execution time
-        let mut executor = TimeoutExecutor::new(
-            InProcessExecutor::new(
-                &mut harness,
-                tuple_list!(edges_observer, time_observer, backtrace_observer),
-                &mut fuzzer,
-                &mut state,
-                &mut mgr,
-            )?,
-            timeout_ms,
-        );
+        let mut executor = ScalingTimeoutExecutor {
+            inner: TimeoutExecutor::new(
+                InProcessExecutor::new(
+                    &mut harness,
+                    tuple_list!(edges_observer, time_observer, backtrace_observer),
+                    &mut fuzzer,
+                    &mut state,
+                    &mut mgr,
+                )?,
+                timeout_ms,
+            ),
+            base: timeout_ms,
+            per_kb: timeout_per_kb,
+        };
 
         // Setup a tracing stage in which we log comparisons
         let tracing = TracingStage::new(InProcessExecutor::new(
@@ -331,34 +2524,85 @@ pub fn main() {
             &mut state,
             &mut mgr,
         )?);
+        let skippable_tracing = SkippableStage::new(tracing, |_s| cmplog_enabled.into());
+
+        // Import the new files of another fuzzer, at most once per sync interval
+        let sync = SyncFromDiskStage::new(
+            foreign_sync_dir.clone().unwrap_or_default(),
+            |_fuzzer: &mut _, _state: &mut _, path: &Path| {
+                Ok(GeneralizedInput::new(fs::read(path)?))
+            },
+        );
+        let sync_enabled = foreign_sync_dir.is_some();
+        let mut last_sync = current_time();
+        let skippable_sync = SkippableStage::new(sync, move |_s| {
+            let now = current_time();
+            let due = sync_enabled && now - last_sync >= sync_interval;
+            if due {
+                last_sync = now;
+            }
+            due.into()
+        });
 
         // The order of the stages matter!
         let mut stages = tuple_list!(
-            skippable_generalization,
-            calibration,
-            tracing,
-            i2s,
-            power,
-            skippable_grimoire
+            TimedStage::new("generalization", skippable_generalization, profile_stages),
+            TimedStage::new("calibration", skippable_calibration, profile_stages),
+            TimedStage::new("tracing", skippable_tracing, profile_stages),
+            TimedStage::new("i2s", skippable_i2s, profile_stages),
+            TimedStage::new("power", skippable_power, profile_stages),
+            TimedStage::new("havoc", skippable_havoc, profile_stages),
+            TimedStage::new("grimoire", skippable_grimoire, profile_stages),
+            TimedStage::new("sync", skippable_sync, profile_stages)
         );
 
         // In case the corpus is empty (on first run), reset
         if state.corpus().count() < 1 {
-            if input_dirs.is_empty() {
-                // Generator of printable bytearrays of max size 32
-                let mut generator =
-                    GeneralizedInputBytesGenerator::from(RandBytesGenerator::new(32));
-
-                // Generate 8 initial inputs
-                state
-                    .generate_initial_inputs(
+            if input_dirs.is_empty() && input_files.is_empty() {
+                // Generate gen_count initial inputs of max size gen_max_size,
+                // printable ones with --printable, e.g. for text parsers
+                let generated = if printable {
+                    let mut generator = GeneralizedInputBytesGenerator::from(
+                        RandPrintablesGenerator::new(gen_max_size),
+                    );
+                    state.generate_initial_inputs(
+                        &mut fuzzer,
+                        &mut executor,
+                        &mut generator,
+                        &mut mgr,
+                        gen_count,
+                    )
+                } else {
+                    let mut generator =
+                        GeneralizedInputBytesGenerator::from(RandBytesGenerator::new(gen_max_size));
+                    state.generate_initial_inputs(
                         &mut fuzzer,
                         &mut executor,
                         &mut generator,
                         &mut mgr,
-                        8,
+                        gen_count,
                     )
-                    .expect("Failed to generate the initial corpus");
+                };
+                generated.expect("Failed to generate the initial corpus");
+                // With a dictionary, also try combinations of its tokens
+                let tokens = state
+                    .metadata()
+                    .get::<Tokens>()
+                    .map_or_else(Vec::new, |tokens| tokens.tokens().to_vec());
+                if !tokens.is_empty() {
+                    for _ in 0..gen_count {
+                        let bytes = token_seed(state.rand_mut(), &tokens, gen_max_size);
+                        if bytes.is_empty() {
+                            continue;
+                        }
+                        fuzzer.evaluate_input(
+                            &mut state,
+                            &mut executor,
+                            &mut mgr,
+                            GeneralizedInput::new(bytes),
+                        )?;
+                    }
+                }
                 println!(
                     "We imported {} inputs from the generator.",
                     state.corpus().count()
@@ -371,17 +2615,108 @@ pub fn main() {
                     .unwrap_or_else(|_| {
                         panic!("Failed to load initial corpus at {:?}", &input_dirs)
                     });
+                for file in &input_files {
+                    let bytes = read_seed(file, compress_corpus)
+                        .unwrap_or_else(|_| panic!("Failed to read seed file {:?}", file));
+                    fuzzer.evaluate_input(
+                        &mut state,
+                        &mut executor,
+                        &mut mgr,
+                        GeneralizedInput::new(bytes),
+                    )?;
+                }
                 println!("We imported {} inputs from disk.", state.corpus().count());
             }
         }
 
-        fuzzer.fuzz_loop(&mut stages, &mut executor, &mut state, &mut mgr)?;
-        Ok(())
+        if import_only {
+            mgr.send_exiting()?;
+            return Err(Error::ShuttingDown);
+        }
+
+        if core_id == first_core {
+            let imported = state
+                .metadata()
+                .get::<ImportedSolutionsMetadata>()
+                .map_or(0, |imported| imported.imported);
+            for (i, file) in solution_files.iter().enumerate().skip(imported) {
+                state.add_metadata(ImportedSolutionsMetadata { imported: i + 1 });
+                let bytes = fs::read(file)
+                    .unwrap_or_else(|_| panic!("Failed to read solution file {:?}", file));
+                fuzzer.evaluate_input(
+                    &mut state,
+                    &mut executor,
+                    &mut mgr,
+                    GeneralizedInput::new(bytes),
+                )?;
+            }
+        }
+
+        let mut stop = stop_condition;
+        stop.start(current_time(), *state.executions(), state.corpus().count());
+        let mut last = current_time();
+        loop {
+            match stop.check(
+                current_time(),
+                SHUTDOWN_REQUESTED.load(Ordering::Relaxed),
+                *state.executions(),
+                state.corpus().count(),
+                state.solutions().count(),
+            ) {
+                Some(Stop::Plateau(plateau)) => {
+                    // The stdout of the clients is discarded, the broker prints the reason
+                    let _ = fs::write(
+                        &plateau_file,
+                        format!(
+                            "Stopped after no new coverage was found for {}s",
+                            plateau.as_secs()
+                        ),
+                    );
+                    break;
+                }
+                Some(Stop::Reset) => {
+                    // Store the state, the restarter will then respawn us in a fresh process
+                    mgr.on_restart(&mut state)?;
+                    return Ok(());
+                }
+                Some(_) => break,
+                None => (),
+            }
+            for crash in state.solutions_mut().take_to_minimize() {
+                auto_tmin(&crash, &minimized_dir, timeout_ms, tmin_iters);
+            }
+            fuzzer.fuzz_one(&mut stages, &mut executor, &mut state, &mut mgr)?;
+            if profile_stages && current_time() - last >= MONITOR_TIMEOUT {
+                let times = state
+                    .metadata()
+                    .get::<StageTimesMetadata>()
+                    .map_or_else(Vec::new, |times| times.micros.clone());
+                for (stage, micros) in times {
+                    mgr.fire(
+                        &mut state,
+                        Event::UpdateUserStats {
+                            name: format!("stage {}", stage),
+                            value: UserStats::Number(micros),
+                            phantom: PhantomData,
+                        },
+                    )?;
+                }
+            }
+            last = mgr.maybe_report_progress(&mut state, last, MONITOR_TIMEOUT)?;
+        }
+
+        // The corpora write each testcase to disk when it is added, so all of them are there
+        // now. Report the last stats, and tell the restarter that we are done, so that it does
+        // not respawn us
+        mgr.maybe_report_progress(&mut state, last, Duration::ZERO)?;
+        mgr.send_exiting()?;
+        Err(Error::ShuttingDown)
     };
 
+    let broker_stopper = spawn_broker_stopper(cores.ids.len(), respawners_dir.clone());
     match Launcher::builder()
         .shmem_provider(shmem_provider)
-        .configuration(EventConfig::from_build_id())
+        .configuration(configuration)
         .monitor(monitor)
         .run_client(&mut run_client)
         .cores(&cores)
@@ -392,6 +2727,139 @@ pub fn main() {
         .launch()
     {
         Ok(_) | Err(Error::ShuttingDown) => (),
-        Err(e) => panic!("{:?}", e),
+        Err(e) => return Err(e),
     };
+    // The clients and their respawners return from `launch` too once they are done, the rest
+    // is up to the main process, which runs the broker
+    if std::process::id() != main_pid {
+        return Ok(0);
+    }
+    LAUNCH_RETURNED.store(true, Ordering::Relaxed);
+    let _ = broker_stopper.join();
+    let _ = fs::remove_dir_all(&respawners_dir);
+
+    if let Ok(diagnostic) = fs::read_to_string(&startup_hang_file) {
+        let _ = fs::remove_file(&startup_hang_file);
+        return Err(Error::illegal_state(diagnostic));
+    }
+
+    if import_only {
+        println!(
+            "Kept {} seeds in {:?}",
+            count_solutions(&corpus_dir),
+            &corpus_dir
+        );
+        return Ok(0);
+    }
+
+    if let Ok(reason) = fs::read_to_string(&plateau_file) {
+        let _ = fs::remove_file(&plateau_file);
+        println!("{}", reason);
+    }
+
+    // Only the broker has seen the stats of the clients
+    if let Some(summary) = *summary.borrow() {
+        let path = output_dir.join("summary.json");
+        if let Err(e) = summary.write(&path) {
+            eprintln!("Failed to write the summary to {:?}: {}", &path, e);
+        }
+    }
+
+    let solutions = count_all_solutions().saturating_sub(solutions_before);
+    if solutions == 0 {
+        println!(
+            "Fuzzing finished after {}s, no solution found",
+            (current_time() - start_time).as_secs()
+        );
+    } else {
+        println!(
+            "Fuzzing finished after {}s, {} solutions found in {:?}",
+            (current_time() - start_time).as_secs(),
+            solutions,
+            solutions_dirs(&output_dir, afl_layout)
+        );
+    }
+    if let Some(summary) = *summary.borrow() {
+        println!(
+            "{} executions ({}/s), {} corpus entries, {}/{} edges",
+            summary.executions,
+            summary.exec_sec,
+            summary.corpus,
+            summary.edges,
+            summary.total_edges
+        );
+    }
+    Ok(solutions)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Stands in for the harness of a fuzz target, which the unit tests do not link
+    #[no_mangle]
+    #[allow(improper_ctypes_definitions)]
+    extern "C" fn rust_fuzzer_test_input(_input: &[u8]) -> i32 {
+        0
+    }
+
+    #[no_mangle]
+    extern "C" fn rust_fuzzer_initialize() {}
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(
+            timeout_from_millis_str("1500").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            duration_from_secs_str("60").unwrap(),
+            Duration::from_secs(60)
+        );
+        assert!(timeout_from_millis_str("1.5").is_err());
+        assert!(duration_from_secs_str("-1").is_err());
+    }
+
+    #[test]
+    fn parse_on_off() {
+        assert!(on_off_from_str("on").unwrap());
+        assert!(!on_off_from_str("off").unwrap());
+        assert!(on_off_from_str("yes").is_err());
+        assert!(on_off_from_str("ON").is_err());
+    }
+
+    #[test]
+    fn parse_power_schedule() {
+        assert!(matches!(
+            power_schedule_from_str("explore"),
+            Ok(PowerSchedule::EXPLORE)
+        ));
+        assert!(matches!(
+            power_schedule_from_str("quad"),
+            Ok(PowerSchedule::QUAD)
+        ));
+        assert!(power_schedule_from_str("rare").is_err());
+    }
+
+    #[test]
+    fn parse_ranges() {
+        assert_eq!(stack_pow_from_str("1").unwrap(), 1);
+        assert_eq!(stack_pow_from_str("10").unwrap(), 10);
+        assert!(stack_pow_from_str("0").is_err());
+        assert!(stack_pow_from_str("11").is_err());
+        assert_eq!(mopt_iterations_from_str("64").unwrap(), 64);
+        assert!(mopt_iterations_from_str("0").is_err());
+        assert!(mopt_iterations_from_str("65").is_err());
+        assert!(mopt_iterations_from_str("many").is_err());
+    }
+
+    #[test]
+    fn unescape_tokens() {
+        assert_eq!(unescape_token("GET").unwrap(), b"GET");
+        assert_eq!(unescape_token(r#"\\\"\x00\xfF"#).unwrap(), b"\\\"\x00\xff");
+        assert!(unescape_token(r"\").is_err());
+        assert!(unescape_token(r"\n").is_err());
+        assert!(unescape_token(r"\x4").is_err());
+        assert!(unescape_token(r"\xzz").is_err());
+    }
 }
@@ -0,0 +1,193 @@
+//! The corpus schedulers that can be picked on the commandline, and the minimizers they are
+//! wrapped in with `--favor`
+
+use clap::ValueEnum;
+use core::marker::PhantomData;
+
+use libafl::{
+    bolts::HasLen,
+    corpus::Testcase,
+    feedbacks::MapIndexesMetadata,
+    inputs::Input,
+    schedulers::{
+        powersched::PowerSchedule, IndexesLenTimeMinimizerScheduler, MinimizerScheduler,
+        PowerQueueScheduler, QueueScheduler, Scheduler, StdWeightedScheduler, TestcaseScore,
+    },
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// The kinds of corpus schedulers that can be picked on the commandline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SchedulerKind {
+    /// Pick the testcases in a round-robin fashion
+    Queue,
+    /// Pick the testcases randomly, weighted by their power score
+    Weighted,
+    /// Pick the testcases in a round-robin fashion, fuzzing each according to its power score
+    Powerqueue,
+}
+
+/// The corpus scheduler picked on the commandline
+pub(crate) enum RuntimeScheduler<I, S> {
+    Queue(QueueScheduler),
+    Weighted(StdWeightedScheduler<I, S>),
+    PowerQueue(PowerQueueScheduler<I, S>),
+}
+
+impl<I, S> RuntimeScheduler<I, S>
+where
+    I: Input,
+{
+    pub(crate) fn new(kind: SchedulerKind, schedule: PowerSchedule) -> Self {
+        match kind {
+            SchedulerKind::Queue => RuntimeScheduler::Queue(QueueScheduler::new()),
+            SchedulerKind::Weighted => {
+                RuntimeScheduler::Weighted(StdWeightedScheduler::with_schedule(schedule))
+            }
+            SchedulerKind::Powerqueue => {
+                RuntimeScheduler::PowerQueue(PowerQueueScheduler::new(schedule))
+            }
+        }
+    }
+}
+
+impl<I, S> Scheduler<I, S> for RuntimeScheduler<I, S>
+where
+    I: Input,
+    QueueScheduler: Scheduler<I, S>,
+    StdWeightedScheduler<I, S>: Scheduler<I, S>,
+    PowerQueueScheduler<I, S>: Scheduler<I, S>,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        dispatch!(self => [Queue, Weighted, PowerQueue], |scheduler| scheduler.on_add(state, idx))
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        dispatch!(self => [Queue, Weighted, PowerQueue], |scheduler| {
+            scheduler.on_replace(state, idx, testcase)
+        })
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        dispatch!(self => [Queue, Weighted, PowerQueue], |scheduler| {
+            scheduler.on_remove(state, idx, testcase)
+        })
+    }
+
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        dispatch!(self => [Queue, Weighted, PowerQueue], |scheduler| scheduler.next(state))
+    }
+}
+
+/// What the scheduler favors among the testcases that cover the same edges, picked on the
+/// commandline. Without a choice, the product of their length and execution time is favored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Favor {
+    /// Favor none of them, the scheduler picks from the whole corpus
+    Coverage,
+    /// Favor the shortest testcase of each edge
+    Short,
+    /// Favor the fastest testcase of each edge
+    Fast,
+}
+
+/// Scores testcases by their length, for `--favor short`
+#[derive(Debug, Clone)]
+pub(crate) struct LenTestcaseScore<I, S> {
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, S> TestcaseScore<I, S> for LenTestcaseScore<I, S>
+where
+    I: Input + HasLen,
+    S: HasMetadata + HasCorpus<I>,
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn compute(entry: &mut Testcase<I>, _state: &S) -> Result<f64, Error> {
+        Ok(entry.cached_len()? as f64)
+    }
+}
+
+/// Scores testcases by their execution time, for `--favor fast`
+#[derive(Debug, Clone)]
+pub(crate) struct TimeTestcaseScore<I, S> {
+    phantom: PhantomData<(I, S)>,
+}
+
+impl<I, S> TestcaseScore<I, S> for TimeTestcaseScore<I, S>
+where
+    I: Input,
+    S: HasMetadata + HasCorpus<I>,
+{
+    #[allow(clippy::cast_precision_loss)]
+    fn compute(entry: &mut Testcase<I>, _state: &S) -> Result<f64, Error> {
+        Ok(entry
+            .exec_time()
+            .map_or(1, |exec_time| exec_time.as_micros()) as f64)
+    }
+}
+
+/// The corpus scheduler wrapped in the minimizer picked with `--favor`
+pub(crate) enum FavorScheduler<CS, I, S>
+where
+    I: Input,
+{
+    LenTime(IndexesLenTimeMinimizerScheduler<CS, I, S>),
+    Len(MinimizerScheduler<CS, LenTestcaseScore<I, S>, I, MapIndexesMetadata, S>),
+    Time(MinimizerScheduler<CS, TimeTestcaseScore<I, S>, I, MapIndexesMetadata, S>),
+    Coverage(CS),
+}
+
+impl<CS, I, S> FavorScheduler<CS, I, S>
+where
+    I: Input,
+{
+    pub(crate) fn new(favor: Option<Favor>, base: CS) -> Self {
+        match favor {
+            None => FavorScheduler::LenTime(IndexesLenTimeMinimizerScheduler::new(base)),
+            Some(Favor::Short) => FavorScheduler::Len(MinimizerScheduler::new(base)),
+            Some(Favor::Fast) => FavorScheduler::Time(MinimizerScheduler::new(base)),
+            Some(Favor::Coverage) => FavorScheduler::Coverage(base),
+        }
+    }
+}
+
+impl<CS, I, S> Scheduler<I, S> for FavorScheduler<CS, I, S>
+where
+    I: Input,
+    CS: Scheduler<I, S>,
+    IndexesLenTimeMinimizerScheduler<CS, I, S>: Scheduler<I, S>,
+    MinimizerScheduler<CS, LenTestcaseScore<I, S>, I, MapIndexesMetadata, S>: Scheduler<I, S>,
+    MinimizerScheduler<CS, TimeTestcaseScore<I, S>, I, MapIndexesMetadata, S>: Scheduler<I, S>,
+{
+    fn on_add(&self, state: &mut S, idx: usize) -> Result<(), Error> {
+        dispatch!(self => [LenTime, Len, Time, Coverage], |scheduler| scheduler.on_add(state, idx))
+    }
+
+    fn on_replace(&self, state: &mut S, idx: usize, testcase: &Testcase<I>) -> Result<(), Error> {
+        dispatch!(self => [LenTime, Len, Time, Coverage], |scheduler| {
+            scheduler.on_replace(state, idx, testcase)
+        })
+    }
+
+    fn on_remove(
+        &self,
+        state: &mut S,
+        idx: usize,
+        testcase: &Option<Testcase<I>>,
+    ) -> Result<(), Error> {
+        dispatch!(self => [LenTime, Len, Time, Coverage], |scheduler| {
+            scheduler.on_remove(state, idx, testcase)
+        })
+    }
+
+    fn next(&self, state: &mut S) -> Result<usize, Error> {
+        dispatch!(self => [LenTime, Len, Time, Coverage], |scheduler| scheduler.next(state))
+    }
+}
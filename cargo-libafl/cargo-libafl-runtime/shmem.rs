@@ -0,0 +1,151 @@
+//! The shared memory provider picked on the commandline, used by the launcher, the broker
+//! and the clients
+
+use clap::ValueEnum;
+use std::sync::OnceLock;
+
+use libafl::{
+    bolts::{
+        shmem::{
+            unix_shmem::{ashmem::AshmemShMemProvider, MmapShMemProvider},
+            ShMem, ShMemId, ShMemProvider, StdShMemProvider,
+        },
+        AsMutSlice, AsSlice,
+    },
+    Error,
+};
+
+/// The size of the shared memory mapped at startup to check that the provider works,
+/// as much as the message pages of the clients need
+const SHMEM_PROBE_SIZE: usize = 1 << 20;
+
+/// The kinds of shared memory providers that can be picked on the commandline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ShMemKind {
+    /// The default provider of `LibAFL` for this OS
+    Std,
+    /// POSIX shared memory, in `/dev/shm` on Linux
+    Mmap,
+    /// Android's `/dev/ashmem`
+    Ashmem,
+}
+
+/// The kind picked on the commandline, read by [`RuntimeShMemProvider::new`] as `LibAFL`
+/// creates new providers in the clients
+static SHMEM_KIND: OnceLock<ShMemKind> = OnceLock::new();
+
+/// The shared memory provider picked on the commandline
+#[derive(Clone, Debug)]
+pub(crate) enum RuntimeShMemProvider {
+    Std(StdShMemProvider),
+    Mmap(MmapShMemProvider),
+    Ashmem(AshmemShMemProvider),
+}
+
+/// A shared memory of a [`RuntimeShMemProvider`]
+#[derive(Clone, Debug)]
+pub(crate) enum RuntimeShMem {
+    Std(<StdShMemProvider as ShMemProvider>::ShMem),
+    Mmap(<MmapShMemProvider as ShMemProvider>::ShMem),
+    Ashmem(<AshmemShMemProvider as ShMemProvider>::ShMem),
+}
+
+impl AsSlice for RuntimeShMem {
+    type Entry = u8;
+
+    fn as_slice(&self) -> &[u8] {
+        dispatch!(self => [Std, Mmap, Ashmem], |shmem| shmem.as_slice())
+    }
+}
+
+impl AsMutSlice for RuntimeShMem {
+    type Entry = u8;
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        dispatch!(self => [Std, Mmap, Ashmem], |shmem| shmem.as_mut_slice())
+    }
+}
+
+impl ShMem for RuntimeShMem {
+    fn id(&self) -> ShMemId {
+        dispatch!(self => [Std, Mmap, Ashmem], |shmem| shmem.id())
+    }
+
+    fn len(&self) -> usize {
+        dispatch!(self => [Std, Mmap, Ashmem], |shmem| shmem.len())
+    }
+}
+
+impl Default for RuntimeShMemProvider {
+    fn default() -> Self {
+        Self::new().unwrap()
+    }
+}
+
+impl ShMemProvider for RuntimeShMemProvider {
+    type ShMem = RuntimeShMem;
+
+    fn new() -> Result<Self, Error> {
+        Ok(match SHMEM_KIND.get().copied().unwrap_or(ShMemKind::Std) {
+            ShMemKind::Std => RuntimeShMemProvider::Std(StdShMemProvider::new()?),
+            ShMemKind::Mmap => RuntimeShMemProvider::Mmap(MmapShMemProvider::new()?),
+            ShMemKind::Ashmem => RuntimeShMemProvider::Ashmem(AshmemShMemProvider::new()?),
+        })
+    }
+
+    fn new_shmem(&mut self, map_size: usize) -> Result<Self::ShMem, Error> {
+        Ok(
+            dispatch!(self => [Std, Mmap, Ashmem] => RuntimeShMem, |provider| {
+                provider.new_shmem(map_size)?
+            }),
+        )
+    }
+
+    fn shmem_from_id_and_size(&mut self, id: ShMemId, size: usize) -> Result<Self::ShMem, Error> {
+        Ok(
+            dispatch!(self => [Std, Mmap, Ashmem] => RuntimeShMem, |provider| {
+                provider.shmem_from_id_and_size(id, size)?
+            }),
+        )
+    }
+
+    fn pre_fork(&mut self) -> Result<(), Error> {
+        dispatch!(self => [Std, Mmap, Ashmem], |provider| provider.pre_fork())
+    }
+
+    fn post_fork(&mut self, is_child: bool) -> Result<(), Error> {
+        dispatch!(self => [Std, Mmap, Ashmem], |provider| provider.post_fork(is_child))
+    }
+
+    fn release_shmem(&mut self, shmem: &mut Self::ShMem) {
+        match (self, shmem) {
+            (RuntimeShMemProvider::Std(provider), RuntimeShMem::Std(shmem)) => {
+                provider.release_shmem(shmem);
+            }
+            (RuntimeShMemProvider::Mmap(provider), RuntimeShMem::Mmap(shmem)) => {
+                provider.release_shmem(shmem);
+            }
+            (RuntimeShMemProvider::Ashmem(provider), RuntimeShMem::Ashmem(shmem)) => {
+                provider.release_shmem(shmem);
+            }
+            _ => unreachable!("Shared memory released by another kind of provider"),
+        }
+    }
+}
+
+/// Creates the shared memory provider picked on the commandline, and checks that it can map
+/// memory, as in containers `/dev/shm` may be missing or too small for the fuzzer
+pub(crate) fn shmem_provider(kind: ShMemKind) -> Result<RuntimeShMemProvider, Error> {
+    // The clients create their own providers, of the same kind
+    let _ = SHMEM_KIND.set(kind);
+    let hint = |err: Error| {
+        Error::illegal_state(format!(
+            "Failed to set up shared memory with --shmem {:?}: {}. If /dev/shm is missing or too small, e.g. in a container, try another --shmem, or mount a larger tmpfs on it (`mount -t tmpfs -o size=1g tmpfs /dev/shm`, or `docker run --shm-size=1g`)",
+            kind, err
+        ))
+    };
+    let mut provider = RuntimeShMemProvider::new().map_err(hint)?;
+    let mut probe = provider.new_shmem(SHMEM_PROBE_SIZE).map_err(hint)?;
+    provider.release_shmem(&mut probe);
+    Ok(provider)
+}
@@ -0,0 +1,199 @@
+//! When a client stops fuzzing, checked before each fuzzed testcase
+
+use core::time::Duration;
+
+/// Why a client stops fuzzing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stop {
+    /// On `SIGINT` or `SIGTERM`
+    Shutdown,
+    /// At the end of `--max-total-time`
+    Deadline,
+    /// No new coverage was found for the given `--plateau`
+    Plateau(Duration),
+    /// After `--max-executions`
+    MaxExecutions,
+    /// On the first solution with `--exit-on-solution`
+    Solution,
+    /// After `--reset-every` executions, the client restarts in a fresh process
+    Reset,
+}
+
+/// The conditions on which a client stops fuzzing, picked on the commandline, and what it
+/// found since it started
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StopCondition {
+    /// Computed once before launching, so that every client observes the same deadline
+    deadline: Option<Duration>,
+    plateau: Option<Duration>,
+    max_executions: Option<usize>,
+    exit_on_solution: bool,
+    reset_every: Option<usize>,
+    executions_at_start: usize,
+    corpus_count: usize,
+    last_coverage: Duration,
+}
+
+impl StopCondition {
+    pub(crate) fn new(
+        deadline: Option<Duration>,
+        plateau: Option<Duration>,
+        max_executions: Option<usize>,
+        exit_on_solution: bool,
+        reset_every: Option<usize>,
+    ) -> Self {
+        Self {
+            deadline,
+            plateau,
+            max_executions,
+            exit_on_solution,
+            reset_every,
+            executions_at_start: 0,
+            corpus_count: 0,
+            last_coverage: Duration::ZERO,
+        }
+    }
+
+    /// Starts counting the executions until `--reset-every` and the time without new
+    /// coverage from here, in a client that was (re)started at `now`
+    pub(crate) fn start(&mut self, now: Duration, executions: usize, corpus_count: usize) {
+        self.executions_at_start = executions;
+        self.corpus_count = corpus_count;
+        self.last_coverage = now;
+    }
+
+    /// Returns why the client stops at `now`, if it does.
+    /// Only the map feedback adds inputs to the corpus, the time and provenance feedbacks
+    /// never find one interesting, so a growing corpus means new coverage. This includes
+    /// the entries of other clients, as they found coverage that is new to the campaign.
+    /// Crashes restart the client, so `solutions` counts the ones in the restored state.
+    pub(crate) fn check(
+        &mut self,
+        now: Duration,
+        shutdown: bool,
+        executions: usize,
+        corpus_count: usize,
+        solutions: usize,
+    ) -> Option<Stop> {
+        if shutdown {
+            return Some(Stop::Shutdown);
+        }
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            return Some(Stop::Deadline);
+        }
+        if corpus_count > self.corpus_count {
+            self.corpus_count = corpus_count;
+            self.last_coverage = now;
+        }
+        if let Some(plateau) = self.plateau {
+            if now.saturating_sub(self.last_coverage) >= plateau {
+                return Some(Stop::Plateau(plateau));
+            }
+        }
+        if self
+            .max_executions
+            .is_some_and(|max_executions| executions >= max_executions)
+        {
+            return Some(Stop::MaxExecutions);
+        }
+        if self.exit_on_solution && solutions > 0 {
+            return Some(Stop::Solution);
+        }
+        if self.reset_every.is_some_and(|reset_every| {
+            executions.saturating_sub(self.executions_at_start) >= reset_every
+        }) {
+            return Some(Stop::Reset);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn never() -> StopCondition {
+        StopCondition::new(None, None, None, false, None)
+    }
+
+    #[test]
+    fn fuzz_forever_by_default() {
+        let mut stop = never();
+        stop.start(secs(0), 0, 0);
+        assert_eq!(stop.check(secs(1_000_000), false, usize::MAX, 0, 1), None);
+    }
+
+    #[test]
+    fn stop_on_shutdown_first() {
+        let mut stop = StopCondition::new(Some(secs(10)), None, Some(1), true, None);
+        stop.start(secs(0), 0, 0);
+        assert_eq!(stop.check(secs(20), true, 5, 0, 1), Some(Stop::Shutdown));
+        assert_eq!(stop.check(secs(20), false, 5, 0, 1), Some(Stop::Deadline));
+    }
+
+    #[test]
+    fn stop_at_the_deadline() {
+        let mut stop = StopCondition::new(Some(secs(100)), None, None, false, None);
+        // A client restarted late still stops at the deadline of the campaign
+        stop.start(secs(90), 0, 0);
+        assert_eq!(stop.check(secs(99), false, 0, 0, 0), None);
+        assert_eq!(stop.check(secs(100), false, 0, 0, 0), Some(Stop::Deadline));
+    }
+
+    #[test]
+    fn stop_on_a_plateau() {
+        let mut stop = StopCondition::new(None, Some(secs(10)), None, false, None);
+        stop.start(secs(0), 0, 3);
+        assert_eq!(stop.check(secs(9), false, 0, 3, 0), None);
+        // New coverage restarts the plateau
+        assert_eq!(stop.check(secs(9), false, 0, 4, 0), None);
+        assert_eq!(stop.check(secs(18), false, 0, 4, 0), None);
+        assert_eq!(
+            stop.check(secs(19), false, 0, 4, 0),
+            Some(Stop::Plateau(secs(10)))
+        );
+    }
+
+    #[test]
+    fn plateau_starts_with_the_client() {
+        let mut stop = StopCondition::new(None, Some(secs(10)), None, false, None);
+        stop.start(secs(50), 0, 3);
+        assert_eq!(stop.check(secs(55), false, 0, 3, 0), None);
+        assert_eq!(
+            stop.check(secs(60), false, 0, 3, 0),
+            Some(Stop::Plateau(secs(10)))
+        );
+    }
+
+    #[test]
+    fn stop_after_max_executions() {
+        let mut stop = StopCondition::new(None, None, Some(100), false, None);
+        // The executions are counted over restarts
+        stop.start(secs(0), 90, 0);
+        assert_eq!(stop.check(secs(0), false, 99, 0, 0), None);
+        assert_eq!(
+            stop.check(secs(0), false, 100, 0, 0),
+            Some(Stop::MaxExecutions)
+        );
+    }
+
+    #[test]
+    fn stop_on_solution() {
+        let mut stop = StopCondition::new(None, None, None, true, None);
+        stop.start(secs(0), 0, 0);
+        assert_eq!(stop.check(secs(0), false, 0, 0, 0), None);
+        assert_eq!(stop.check(secs(0), false, 0, 0, 1), Some(Stop::Solution));
+    }
+
+    #[test]
+    fn reset_after_executions_of_this_client() {
+        let mut stop = StopCondition::new(None, None, None, false, Some(50));
+        stop.start(secs(0), 1000, 0);
+        assert_eq!(stop.check(secs(0), false, 1049, 0, 0), None);
+        assert_eq!(stop.check(secs(0), false, 1050, 0, 0), Some(Stop::Reset));
+    }
+}
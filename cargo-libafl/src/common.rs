@@ -1,16 +1,32 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use xdg::BaseDirectories;
 
 fn xdg_dir() -> BaseDirectories {
     let prefix = Path::new("cargo-libafl")
         .join(rustc_version())
-        .join(pkg_version());
+        .join(pkg_version())
+        .join(features_version());
     BaseDirectories::with_prefix(prefix).unwrap()
 }
 
 const SHORT_COMMIT_HASH_LEN: usize = 7;
 
+/// The source files of the runtime crate, copied next to its manifest by `build.rs`
+#[allow(dead_code)]
+pub const RUNTIME_SOURCES: &[&str] = &[
+    "runtime.rs",
+    "dispatch.rs",
+    "corpus.rs",
+    "monitor.rs",
+    "scheduler.rs",
+    "shmem.rs",
+    "stop.rs",
+];
+
 pub fn rustc_version() -> String {
     let version_meta = rustc_version::version_meta().unwrap();
     let mut ret = String::from("rustc-");
@@ -32,26 +48,142 @@ fn pkg_version() -> String {
     ret
 }
 
+/// The features of the runtime, enabled by the features of the same name of cargo-libafl
+pub fn runtime_features() -> Vec<&'static str> {
+    let mut features = vec![];
+    if cfg!(feature = "tui") {
+        features.push("tui");
+    }
+    features
+}
+
+/// Keeps the archives built with different runtime features apart
+fn features_version() -> String {
+    let features = runtime_features();
+    if features.is_empty() {
+        String::from("features-none")
+    } else {
+        format!("features-{}", features.join("-"))
+    }
+}
+
 // Place directories inside the crate when building for docs.rs.
 // (Modifying system paths are forbidden.)
 
 #[cfg(docsrs)]
-pub fn runtime_dir() -> PathBuf {
+pub fn runtime_dir(_triple: &str, _rustflags: &[String]) -> PathBuf {
     let path = PathBuf::from("./cargo-libafl-dummy");
     std::fs::create_dir_all(&path).unwrap();
     path
 }
 
+/// The dir of the runtime archive for the target `triple`, each triple has its own,
+/// and so has each set of extra `rustflags` the runtime is built with
 #[cfg(not(docsrs))]
-pub fn runtime_dir() -> PathBuf {
+pub fn runtime_dir(triple: &str, rustflags: &[String]) -> PathBuf {
     if let Some(custom_dir) = std::env::var_os("CUSTOM_LIBAFL_RUNTIME") {
         PathBuf::from(custom_dir)
     } else {
-        xdg_dir().create_data_directory("cargo-libafl").unwrap()
+        let mut dir = Path::new("cargo-libafl").join(triple);
+        if !rustflags.is_empty() {
+            dir.push(rustflags_version(rustflags));
+        }
+        xdg_dir().create_data_directory(dir).unwrap()
     }
 }
 
+/// Keeps the runtimes built with different extra rustflags apart
+#[cfg(not(docsrs))]
+fn rustflags_version(rustflags: &[String]) -> String {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    let mut hasher = DefaultHasher::new();
+    rustflags.hash(&mut hasher);
+    format!("rustflags-{:016x}", hasher.finish())
+}
+
+/// The data dir of the runtimes cached for this rustc version, cargo-libafl version and set
+/// of features, or of the runtimes of all versions with `all`
 #[allow(dead_code)]
-pub fn archive_file_path() -> PathBuf {
-    runtime_dir().join("libcargo_libafl_runtime.a")
+pub fn runtime_cache_dir(all: bool) -> PathBuf {
+    if all {
+        BaseDirectories::with_prefix("cargo-libafl")
+            .unwrap()
+            .get_data_home()
+    } else {
+        xdg_dir().get_data_home()
+    }
+}
+
+pub fn archive_file_path(triple: &str, rustflags: &[String]) -> PathBuf {
+    runtime_dir(triple, rustflags).join("libcargo_libafl_runtime.a")
+}
+
+/// The command that builds the runtime crate in `manifest_dir` for `triple`,
+/// with the features of [`runtime_features`] and the extra `rustflags`
+pub fn runtime_build_command(
+    manifest_dir: &Path,
+    target_dir: &Path,
+    triple: &str,
+    rustflags: &[String],
+) -> Command {
+    let mut cmd = Command::new("cargo");
+    // The runtime always gets its own target dir, whatever `CARGO_TARGET_DIR` the outer
+    // build uses, so that the archive is found and doesn't clash with the fuzz targets
+    cmd.current_dir(manifest_dir)
+        .env_remove("CARGO_TARGET_DIR")
+        .arg("build")
+        .arg("--target-dir")
+        .arg(target_dir)
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        .arg("--target")
+        .arg(triple)
+        .arg("--release")
+        .arg("--no-default-features");
+    // Each set of features is cached in its own dir, see `runtime_dir`
+    let features = runtime_features();
+    if !features.is_empty() {
+        cmd.arg("--features").arg(features.join(","));
+    }
+    // Appended to `RUSTFLAGS` like for the fuzz targets, each set is cached in its own dir
+    if !rustflags.is_empty() {
+        let mut flags = std::env::var("RUSTFLAGS").unwrap_or_default();
+        for flag in rustflags {
+            flags.push(' ');
+            flags.push_str(flag);
+        }
+        cmd.env("RUSTFLAGS", flags.trim_start());
+    }
+    cmd
+}
+
+/// The archive built by [`runtime_build_command`]
+pub fn built_archive(target_dir: &Path, triple: &str) -> PathBuf {
+    target_dir
+        .join(triple)
+        .join("release")
+        .join("libcargo_libafl_runtime.a")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rustflags_cache_key() {
+        let flags = vec![String::from("-Ctarget-cpu=native")];
+        let key = rustflags_version(&flags);
+        assert!(key.starts_with("rustflags-"));
+        assert_eq!(key.len(), "rustflags-".len() + 16);
+        assert_eq!(key, rustflags_version(&flags.clone()));
+        assert_ne!(key, rustflags_version(&[String::from("-Copt-level=2")]));
+        // The flags are passed in order, so the order is part of the key
+        let pair = [String::from("-Ca"), String::from("-Cb")];
+        let swapped = [String::from("-Cb"), String::from("-Ca")];
+        assert_ne!(rustflags_version(&pair), rustflags_version(&swapped));
+    }
 }
@@ -47,10 +47,26 @@ enum Command {
     List(options::List),
 
     /// Run a fuzz target
+    ///
+    /// Exits with 0 when no solution was found, with 77 when the fuzz target found solutions,
+    /// and with 1 on any other error, e.g. a failed build.
     Run(options::Run),
 
     /// Run program on the generated corpus and generate coverage information
     Coverage(options::Coverage),
+
+    /// Minimize a crashing input, preserving the crash
+    Tmin(options::Tmin),
+
+    /// Replay every input of a corpus once, failing if any of them crashes or times out
+    #[clap(alias = "check-corpus")]
+    Verify(options::Verify),
+
+    /// Print the number of inputs in a corpus and the distribution of their sizes
+    Stats(options::Stats),
+
+    /// Remove the cached fuzzer runtimes, and optionally the generated dirs of the fuzz project
+    Clean(options::Clean),
 }
 
 impl RunCommand for Command {
@@ -63,10 +79,18 @@ impl RunCommand for Command {
             Command::Fmt(x) => x.run_command(),
             Command::Run(x) => x.run_command(),
             Command::Coverage(x) => x.run_command(),
+            Command::Tmin(x) => x.run_command(),
+            Command::Verify(x) => x.run_command(),
+            Command::Stats(x) => x.run_command(),
+            Command::Clean(x) => x.run_command(),
         }
     }
 }
 
 fn main() -> Result<()> {
+    // Cargo runs us in place of rustc with `--no-cfg-fuzzing-deps`
+    if std::env::var_os(utils::CFG_FUZZING_WRAPPER_ENV).is_some() {
+        utils::run_cfg_fuzzing_wrapper();
+    }
     Command::from_args().run_command()
 }
@@ -1,13 +1,27 @@
 mod add;
 mod build;
+mod clean;
 mod coverage;
 mod fmt;
 mod init;
 mod list;
 mod run;
+mod stats;
+mod tmin;
+mod verify;
 
 pub use self::{
-    add::Add, build::Build, coverage::Coverage, fmt::Fmt, init::Init, list::List, run::Run,
+    add::Add,
+    build::Build,
+    clean::Clean,
+    coverage::{Coverage, CoverageFormat},
+    fmt::Fmt,
+    init::Init,
+    list::List,
+    run::Run,
+    stats::Stats,
+    tmin::Tmin,
+    verify::Verify,
 };
 
 use clap::{self, Parser};
@@ -17,9 +31,11 @@ use std::{fmt as stdfmt, path::PathBuf};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sanitizer {
     Address,
+    HwAddress,
     Leak,
     Memory,
     Thread,
+    Undefined,
     None,
 }
 
@@ -30,10 +46,12 @@ impl stdfmt::Display for Sanitizer {
             "{}",
             match self {
                 Sanitizer::Address => "address",
+                Sanitizer::HwAddress => "hwaddress",
                 Sanitizer::Leak => "leak",
                 Sanitizer::Memory => "memory",
                 Sanitizer::Thread => "thread",
-                Sanitizer::None => "",
+                Sanitizer::Undefined => "undefined",
+                Sanitizer::None => "none",
             }
         )
     }
@@ -45,15 +63,48 @@ impl FromStr for Sanitizer {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "address" => Ok(Sanitizer::Address),
+            "hwaddress" => Ok(Sanitizer::HwAddress),
             "leak" => Ok(Sanitizer::Leak),
             "memory" => Ok(Sanitizer::Memory),
             "thread" => Ok(Sanitizer::Thread),
+            "undefined" => Ok(Sanitizer::Undefined),
             "none" => Ok(Sanitizer::None),
             _ => Err(format!("unknown sanitizer: {}", s)),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicStrategy {
+    Abort,
+    Unwind,
+}
+
+impl stdfmt::Display for PanicStrategy {
+    fn fmt(&self, f: &mut stdfmt::Formatter) -> stdfmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PanicStrategy::Abort => "abort",
+                PanicStrategy::Unwind => "unwind",
+            }
+        )
+    }
+}
+
+impl FromStr for PanicStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "abort" => Ok(PanicStrategy::Abort),
+            "unwind" => Ok(PanicStrategy::Unwind),
+            _ => Err(format!("unknown panic strategy: {}", s)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Parser, PartialEq, Eq)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct BuildOptions {
@@ -65,6 +116,15 @@ pub struct BuildOptions {
     /// Build artifacts in release mode, with optimizations
     pub release: bool,
 
+    #[clap(long = "profile", conflicts_with_all = &["dev", "release"])]
+    /// Build artifacts with the given Cargo profile
+    pub profile: Option<String>,
+
+    #[clap(long = "debug-info")]
+    /// Build artifacts with full debug info, e.g. for line numbers in crash backtraces.
+    /// Unlike `--dev`, this keeps the optimizations of the selected profile.
+    pub debug_info: bool,
+
     #[clap(short = 'a', long = "debug-assertions")]
     /// Build artifacts with debug assertions and overflow checks enabled (default if not -O)
     pub debug_assertions: bool,
@@ -92,11 +152,14 @@ pub struct BuildOptions {
     #[clap(
         short = 's',
         long = "sanitizer",
-        possible_values(&["address", "leak", "memory", "thread", "none"]),
+        possible_values(&["address", "hwaddress", "leak", "memory", "thread", "undefined", "none"]),
+        use_value_delimiter = true,
         default_value = "address",
     )]
-    /// Use a specific sanitizer
-    pub sanitizer: Sanitizer,
+    /// Use specific sanitizers, as a comma-separated list.
+    /// `undefined` only applies to C/C++ code compiled through `CFLAGS`/`CXXFLAGS`,
+    /// as rustc has no such sanitizer.
+    pub sanitizer: Vec<Sanitizer>,
 
     #[clap(
         name = "triple",
@@ -110,8 +173,14 @@ pub struct BuildOptions {
     /// Unstable (nightly-only) flags to Cargo
     pub unstable_flags: Vec<String>,
 
+    #[clap(long = "rustflags", value_name = "FLAG", allow_hyphen_values = true)]
+    /// Extra flags to rustc, appended after the ones set by cargo-libafl and `RUSTFLAGS`.
+    /// The fuzzer runtime is rebuilt with them on first use, and cached for each set of flags.
+    pub rustflags: Vec<String>,
+
     #[clap(long = "target-dir")]
     /// Target dir option to pass to cargo build.
+    /// Defaults to `CARGO_TARGET_DIR` if set, else to the `target` dir of the fuzz crate.
     pub target_dir: Option<String>,
 
     #[clap(skip = false)]
@@ -132,6 +201,12 @@ pub struct BuildOptions {
     #[clap(long)]
     pub no_cfg_fuzzing: bool,
 
+    /// Only set 'cfg(fuzzing)' for the fuzz crate and the crate being fuzzed, not for their
+    /// dependencies, e.g. for a dependency that is broken with it.
+    /// It builds all crates through cargo-libafl as `RUSTC_WRAPPER`.
+    #[clap(long, conflicts_with = "no-cfg-fuzzing")]
+    pub no_cfg_fuzzing_deps: bool,
+
     #[clap(long)]
     /// Don't build with the `sanitizer-coverage-trace-compares` LLVM argument
     ///
@@ -140,6 +215,46 @@ pub struct BuildOptions {
     /// the `*-trace-compares` instrumentation assumes that the instruction is
     /// available.
     pub no_trace_compares: bool,
+
+    #[clap(
+        long = "detect-leaks",
+        possible_values(&["on", "off"]),
+        default_value = "on",
+        parse(try_from_str = on_off_from_str)
+    )]
+    /// Report memory leaks as crashes, `on` by default like ASAN does.
+    /// Only has an effect with the `address` sanitizer.
+    pub detect_leaks: bool,
+
+    #[clap(long = "panic", possible_values(&["abort", "unwind"]))]
+    /// Build with the given panic strategy, the one of the profile by default.
+    /// Panics crash the fuzz target either way. `abort` makes a faster target,
+    /// but the crash backtraces, and so the deduplication of crashes, are less precise.
+    pub panic: Option<PanicStrategy>,
+
+    #[clap(
+        long = "env",
+        value_name = "KEY=VALUE",
+        parse(try_from_str = env_from_str)
+    )]
+    /// Set an environment variable for cargo and the fuzz target, can be repeated.
+    /// Useful for harnesses that read their configuration from the environment.
+    pub env: Vec<(String, String)>,
+}
+
+fn on_off_from_str(s: &str) -> Result<bool, String> {
+    match s {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("expected `on` or `off`, got: {}", s)),
+    }
+}
+
+fn env_from_str(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected `KEY=VALUE`, got: {}", s)),
+    }
 }
 
 impl stdfmt::Display for BuildOptions {
@@ -152,6 +267,14 @@ impl stdfmt::Display for BuildOptions {
             write!(f, " -O")?;
         }
 
+        if let Some(profile) = &self.profile {
+            write!(f, " --profile={}", profile)?;
+        }
+
+        if self.debug_info {
+            write!(f, " --debug-info")?;
+        }
+
         if self.debug_assertions {
             write!(f, " -a")?;
         }
@@ -172,10 +295,9 @@ impl stdfmt::Display for BuildOptions {
             write!(f, " --features={}", feature)?;
         }
 
-        match self.sanitizer {
-            Sanitizer::None => write!(f, " --sanitizer=none")?,
-            Sanitizer::Address => {}
-            _ => write!(f, " --sanitizer={}", self.sanitizer)?,
+        if self.sanitizer != [Sanitizer::Address] {
+            let sanitizers: Vec<String> = self.sanitizer.iter().map(ToString::to_string).collect();
+            write!(f, " --sanitizer={}", sanitizers.join(","))?;
         }
 
         if self.triple != crate::utils::default_target() {
@@ -186,6 +308,10 @@ impl stdfmt::Display for BuildOptions {
             write!(f, " -Z{}", flag)?;
         }
 
+        for flag in &self.rustflags {
+            write!(f, " --rustflags={}", flag)?;
+        }
+
         if let Some(target_dir) = &self.target_dir {
             write!(f, " --target-dir={}", target_dir)?;
         }
@@ -194,6 +320,22 @@ impl stdfmt::Display for BuildOptions {
             write!(f, " --coverage")?;
         }
 
+        if !self.detect_leaks {
+            write!(f, " --detect-leaks=off")?;
+        }
+
+        if self.no_cfg_fuzzing_deps {
+            write!(f, " --no-cfg-fuzzing-deps")?;
+        }
+
+        if let Some(panic) = self.panic {
+            write!(f, " --panic={}", panic)?;
+        }
+
+        for (key, value) in &self.env {
+            write!(f, " --env={}={}", key, value)?;
+        }
+
         Ok(())
     }
 }
@@ -219,24 +361,48 @@ impl stdfmt::Display for FuzzDirWrapper {
 mod test {
     use super::*;
 
+    #[test]
+    fn parse_env() {
+        assert_eq!(
+            env_from_str("KEY=a=b"),
+            Ok((String::from("KEY"), String::from("a=b")))
+        );
+        assert!(env_from_str("KEY").is_err());
+        assert!(env_from_str("=value").is_err());
+    }
+
+    #[test]
+    fn parse_on_off() {
+        assert_eq!(on_off_from_str("on"), Ok(true));
+        assert_eq!(on_off_from_str("off"), Ok(false));
+        assert!(on_off_from_str("yes").is_err());
+    }
+
     #[test]
     fn display_build_options() {
         let default_opts = BuildOptions {
             dev: false,
             release: false,
+            profile: None,
+            debug_info: false,
             debug_assertions: false,
             verbose: false,
             no_default_features: false,
             all_features: false,
             features: None,
-            sanitizer: Sanitizer::Address,
+            sanitizer: vec![Sanitizer::Address],
             triple: String::from(crate::utils::default_target()),
             unstable_flags: Vec::new(),
+            rustflags: Vec::new(),
             target_dir: None,
             coverage: false,
             strip_dead_code: false,
             no_cfg_fuzzing: false,
+            no_cfg_fuzzing_deps: false,
             no_trace_compares: false,
+            detect_leaks: true,
+            panic: None,
+            env: Vec::new(),
         };
 
         let opts = vec![
@@ -249,6 +415,15 @@ mod test {
                 release: true,
                 ..default_opts.clone()
             },
+            BuildOptions {
+                profile: Some(String::from("fuzz")),
+                ..default_opts.clone()
+            },
+            BuildOptions {
+                debug_info: true,
+                release: true,
+                ..default_opts.clone()
+            },
             BuildOptions {
                 debug_assertions: true,
                 ..default_opts.clone()
@@ -270,7 +445,34 @@ mod test {
                 ..default_opts.clone()
             },
             BuildOptions {
-                sanitizer: Sanitizer::None,
+                detect_leaks: false,
+                ..default_opts.clone()
+            },
+            BuildOptions {
+                panic: Some(PanicStrategy::Abort),
+                ..default_opts.clone()
+            },
+            BuildOptions {
+                no_cfg_fuzzing_deps: true,
+                ..default_opts.clone()
+            },
+            BuildOptions {
+                rustflags: vec![
+                    String::from("-Ctarget-cpu=native"),
+                    String::from("-Copt-level=2"),
+                ],
+                ..default_opts.clone()
+            },
+            BuildOptions {
+                sanitizer: vec![Sanitizer::None],
+                ..default_opts.clone()
+            },
+            BuildOptions {
+                sanitizer: vec![Sanitizer::HwAddress],
+                ..default_opts.clone()
+            },
+            BuildOptions {
+                sanitizer: vec![Sanitizer::Address, Sanitizer::Undefined],
                 ..default_opts.clone()
             },
             BuildOptions {
@@ -285,6 +487,13 @@ mod test {
                 target_dir: Some(String::from("/tmp/test")),
                 ..default_opts.clone()
             },
+            BuildOptions {
+                env: vec![
+                    (String::from("HARNESS_CONFIG"), String::from("a=b")),
+                    (String::from("EMPTY"), String::new()),
+                ],
+                ..default_opts.clone()
+            },
             BuildOptions {
                 coverage: false,
                 ..default_opts
@@ -9,6 +9,10 @@ pub struct Add {
 
     /// Name of the new fuzz target
     pub target: String,
+
+    #[clap(long = "arbitrary", value_name = "TYPE")]
+    /// Generate a structure-aware target, taking a `TYPE` that derives `Arbitrary`
+    pub arbitrary: Option<String>,
 }
 
 impl RunCommand for Add {
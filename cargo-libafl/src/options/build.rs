@@ -16,11 +16,25 @@ pub struct Build {
 
     /// Name of the fuzz target to build, or build all targets if not supplied
     pub target: Option<String>,
+
+    #[clap(long = "dry-run")]
+    /// Print the `cargo build` command line and its environment instead of running it
+    pub dry_run: bool,
+
+    #[clap(long = "keep-going", conflicts_with_all = &["target", "dry-run"])]
+    /// Build all targets one by one and go on after failures, then list the ones that failed
+    pub keep_going: bool,
 }
 
 impl RunCommand for Build {
     fn run_command(&mut self) -> Result<()> {
         let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.clone())?;
-        project.exec_build(&self.build, self.target.as_deref())
+        if self.dry_run {
+            project.print_build_command(&self.build, self.target.as_deref())
+        } else if self.keep_going {
+            project.exec_build_keep_going(&self.build)
+        } else {
+            project.exec_build(&self.build, self.target.as_deref())
+        }
     }
 }
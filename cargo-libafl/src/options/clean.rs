@@ -0,0 +1,43 @@
+use crate::{common, options::FuzzDirWrapper, project::FuzzProject, utils, RunCommand};
+use anyhow::{Context, Result};
+use clap::{self, Parser};
+use std::fs;
+
+#[derive(Clone, Debug, Parser)]
+pub struct Clean {
+    #[clap(long = "all")]
+    /// Remove the cached runtimes of all rustc and cargo-libafl versions, not only of the
+    /// current ones
+    pub all: bool,
+
+    #[clap(long = "project")]
+    /// Also remove the `target`, `corpus`, `artifacts` and `coverage` dirs of the fuzz project
+    pub project: bool,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+}
+
+impl RunCommand for Clean {
+    fn run_command(&mut self) -> Result<()> {
+        let mut dirs = vec![common::runtime_cache_dir(self.all)];
+        if self.project {
+            let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.clone())?;
+            dirs.extend(project.generated_dirs());
+        }
+
+        let mut reclaimed = 0;
+        for dir in dirs {
+            if !dir.exists() {
+                continue;
+            }
+            let size = utils::dir_size(&dir);
+            fs::remove_dir_all(&dir)
+                .with_context(|| format!("failed to remove {}", dir.display()))?;
+            println!("Removed {} ({})", dir.display(), utils::format_size(size));
+            reclaimed += size;
+        }
+        println!("Reclaimed {}", utils::format_size(reclaimed));
+        Ok(())
+    }
+}
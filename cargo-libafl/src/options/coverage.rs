@@ -5,6 +5,41 @@ use crate::{
 };
 use anyhow::Result;
 use clap::{self, Parser};
+use std::{fmt as stdfmt, path::PathBuf, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageFormat {
+    Lcov,
+    Html,
+    Text,
+}
+
+impl stdfmt::Display for CoverageFormat {
+    fn fmt(&self, f: &mut stdfmt::Formatter) -> stdfmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                CoverageFormat::Lcov => "lcov",
+                CoverageFormat::Html => "html",
+                CoverageFormat::Text => "text",
+            }
+        )
+    }
+}
+
+impl FromStr for CoverageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lcov" => Ok(CoverageFormat::Lcov),
+            "html" => Ok(CoverageFormat::Html),
+            "text" => Ok(CoverageFormat::Text),
+            _ => Err(format!("unknown coverage output format: {}", s)),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Parser)]
 pub struct Coverage {
@@ -17,9 +52,28 @@ pub struct Coverage {
     /// Name of the fuzz target
     pub target: String,
 
-    /// Custom corpus directories or artifact files
+    /// Custom corpus directories or artifact files, e.g. a single crash to see the code it
+    /// reaches. Every file directly inside a directory is replayed.
     pub corpus: Vec<String>,
 
+    #[clap(
+        long = "output-format",
+        possible_values(&["lcov", "html", "text"]),
+        default_value = "lcov"
+    )]
+    /// Format of the coverage report written to the coverage directory
+    pub output_format: CoverageFormat,
+
+    #[clap(long = "summary")]
+    /// Also print the line coverage of each function of the fuzzed crate, least covered first
+    pub summary: bool,
+
+    #[clap(long = "merge-only", value_name = "DIR", conflicts_with = "corpus")]
+    /// Skip building and running the target, and only merge the `.profraw` files in this
+    /// directory into the report, e.g. the ones of corpus shards run in parallel. Needs a
+    /// previous coverage build of the target.
+    pub merge_only: Option<PathBuf>,
+
     #[clap(last(true))]
     /// Additional libFuzzer arguments passed through to the binary
     pub args: Vec<String>,
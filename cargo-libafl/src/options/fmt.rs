@@ -19,8 +19,16 @@ pub struct Fmt {
     /// Name of fuzz target
     pub target: String,
 
-    /// Path to the input testcase to debug print
+    /// Path to the input testcase to debug print, or to a directory of testcases
     pub input: PathBuf,
+
+    #[clap(long = "limit")]
+    /// Print at most this many testcases when the input is a directory
+    pub limit: Option<usize>,
+
+    #[clap(long = "hexdump")]
+    /// Also print an offset/hex/ASCII dump of the raw bytes of each testcase
+    pub hexdump: bool,
 }
 
 impl RunCommand for Fmt {
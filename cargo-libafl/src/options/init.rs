@@ -13,6 +13,15 @@ pub struct Init {
     /// Name of the first fuzz target to create
     pub target: String,
 
+    #[clap(long = "arbitrary", value_name = "TYPE")]
+    /// Generate a structure-aware target, taking a `TYPE` that derives `Arbitrary`
+    pub arbitrary: Option<String>,
+
+    #[clap(long = "overwrite")]
+    /// Regenerate the `Cargo.toml` and `.gitignore` of an existing fuzz project, keeping its
+    /// fuzz targets
+    pub overwrite: bool,
+
     #[clap(flatten)]
     pub fuzz_dir_wrapper: FuzzDirWrapper,
 }
@@ -6,12 +6,20 @@ use clap::{self, Parser};
 pub struct List {
     #[clap(flatten)]
     pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long = "detail")]
+    /// Also show the number of corpus entries and crashes found for each target
+    pub detail: bool,
+
+    #[clap(long = "json")]
+    /// Print the targets as a JSON array of objects with their name, path and counts
+    pub json: bool,
 }
 
 impl RunCommand for List {
     fn run_command(&mut self) -> Result<()> {
         let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.clone())?;
-        project.list_targets();
+        project.list_targets(self.detail, self.json);
         Ok(())
     }
 }
@@ -14,12 +14,32 @@ pub struct Run {
     /// Name of the fuzz target
     pub target: String,
 
-    /// Custom corpus directories or artifact files.
+    /// Seed corpus directories or artifact files, in any order.
+    ///
+    /// Every file inside a directory is imported as an initial input, while a file
+    /// given directly (e.g. a crash from `artifacts/`) is imported on its own.
+    /// Directories that do not exist yet are created, unless `--reproduce` is given.
+    ///
+    /// Leading names of other fuzz targets are run one after another instead,
+    /// each on its own `corpus/<target>` directory, with a summary at the end.
     pub corpus: Vec<String>,
 
     #[clap(flatten)]
     pub fuzz_dir_wrapper: FuzzDirWrapper,
 
+    #[clap(long = "reproduce")]
+    /// Run the target once on each corpus file and report how it exited, instead of fuzzing
+    pub reproduce: bool,
+
+    #[clap(long = "max-total-time", value_name = "SECS")]
+    /// Stop fuzzing after the given number of seconds.
+    /// With several targets, the time is split evenly among them.
+    pub max_total_time: Option<u64>,
+
+    #[clap(long = "no-unicode")]
+    /// Disable unicode in the fuzzer UI, for old terminals
+    pub no_unicode: bool,
+
     #[clap(last(true))]
     /// Additional libFuzzer arguments passed through to the binary
     pub args: Vec<String>,
@@ -0,0 +1,28 @@
+use crate::{options::FuzzDirWrapper, project::FuzzProject, RunCommand};
+use anyhow::Result;
+use clap::{self, Parser};
+
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Parser)]
+pub struct Stats {
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    /// Name of the fuzz target
+    pub target: String,
+
+    /// Corpus directory to scan, defaults to the corpus of the target
+    pub corpus: Option<PathBuf>,
+
+    #[clap(long = "json")]
+    /// Print the stats as a JSON object, with the histogram as an array of buckets
+    pub json: bool,
+}
+
+impl RunCommand for Stats {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.clone())?;
+        project.exec_stats(self)
+    }
+}
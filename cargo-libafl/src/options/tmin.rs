@@ -0,0 +1,43 @@
+use crate::{
+    options::{BuildOptions, FuzzDirWrapper},
+    project::FuzzProject,
+    RunCommand,
+};
+use anyhow::Result;
+use clap::{self, Parser};
+
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Parser)]
+pub struct Tmin {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    /// Name of the fuzz target
+    pub target: String,
+
+    /// Path to the crashing input to minimize
+    pub input: PathBuf,
+
+    #[clap(short = 'o', long = "output")]
+    /// Path to write the minimized input to, defaults to `<input>.min`
+    pub output: Option<PathBuf>,
+
+    #[clap(short = 'r', long = "runs", default_value = "1024")]
+    /// Number of minimization attempts in each round
+    pub runs: usize,
+
+    #[clap(last(true))]
+    /// Additional arguments passed through to the binary, e.g. `--timeout`
+    pub args: Vec<String>,
+}
+
+impl RunCommand for Tmin {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.clone())?;
+        project.exec_tmin(self)
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{
+    options::{BuildOptions, FuzzDirWrapper},
+    project::FuzzProject,
+    RunCommand,
+};
+use anyhow::Result;
+use clap::{self, Parser};
+
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Parser)]
+pub struct Verify {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    /// Name of the fuzz target
+    pub target: String,
+
+    /// Corpus directory to replay, defaults to the corpus of the target
+    pub corpus: Option<PathBuf>,
+
+    #[clap(last(true))]
+    /// Additional arguments passed through to the binary, e.g. `--timeout`
+    pub args: Vec<String>,
+}
+
+impl RunCommand for Verify {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.clone())?;
+        project.exec_verify(self)
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     common,
-    options::{self, BuildOptions, Sanitizer},
-    utils::default_target,
+    options::{self, BuildOptions, CoverageFormat, Sanitizer},
+    utils::{self, json_string},
 };
 use anyhow::{anyhow, bail, Context, Result};
 use std::{
@@ -11,13 +11,36 @@ use std::{
     fmt::Write as FmtWrite,
     fs,
     io::{Read, Write},
+    iter,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Command, ExitStatus, Stdio},
     time,
 };
 
 const DEFAULT_FUZZ_DIR: &str = "fuzz";
 
+/// The exit code of the runtime, and of `cargo libafl run`, when solutions were found
+const SOLUTION_EXIT_CODE: i32 = 77;
+
+/// Embeds the runtime source files copied by `build.rs`, see [`common::RUNTIME_SOURCES`]
+macro_rules! runtime_sources {
+    ($($file:literal),+ $(,)?) => {
+        &[$(($file, include_str!(concat!(env!("OUT_DIR"), "/", $file)))),+]
+    };
+}
+
+/// The sources of the runtime as prepared by `build.rs`, to build it for other targets
+const RUNTIME_MANIFEST: &str = include_str!(concat!(env!("OUT_DIR"), "/Cargo.toml"));
+const RUNTIME_SOURCES: &[(&str, &str)] = runtime_sources!(
+    "runtime.rs",
+    "dispatch.rs",
+    "corpus.rs",
+    "monitor.rs",
+    "scheduler.rs",
+    "shmem.rs",
+    "stop.rs",
+);
+
 pub struct FuzzProject {
     /// The project with fuzz targets
     fuzz_dir: PathBuf,
@@ -59,12 +82,22 @@ impl FuzzProject {
         let fuzz_project = project.fuzz_dir();
         let root_project_name = project.root_project_name()?;
 
-        // TODO: check if the project is already initialized
-        fs::create_dir(&fuzz_project)
+        let exists = fuzz_project.join("Cargo.toml").exists()
+            || fuzz_project.join(crate::FUZZ_TARGETS_DIR).exists()
+            || fuzz_project.join(crate::FUZZ_TARGETS_DIR_OLD).exists();
+        if exists && !init.overwrite {
+            bail!(
+                "a fuzz project already exists at {}, pass `--overwrite` to regenerate \
+                 its scaffolding",
+                fuzz_project.display()
+            );
+        }
+        fs::create_dir_all(fuzz_project)
             .with_context(|| format!("failed to create directory {}", fuzz_project.display()))?;
 
-        let fuzz_targets_dir = fuzz_project.join(crate::FUZZ_TARGETS_DIR);
-        fs::create_dir(&fuzz_targets_dir).with_context(|| {
+        // Existing targets stay where they are, also in the legacy `fuzzers` dir
+        let fuzz_targets_dir = project.fuzz_targets_dir();
+        fs::create_dir_all(&fuzz_targets_dir).with_context(|| {
             format!("failed to create directory {}", fuzz_targets_dir.display())
         })?;
 
@@ -82,20 +115,89 @@ impl FuzzProject {
             .write_fmt(gitignore_template!())
             .with_context(|| format!("failed to write to {}", gitignore.display()))?;
 
-        project
-            .create_target_template(&init.target)
-            .with_context(|| {
-                format!(
-                    "could not create template file for target {:?}",
-                    init.target
-                )
-            })?;
+        // The regenerated manifest still lists the existing targets, which are left untouched
+        let mut existing_targets = Vec::new();
+        for entry in fs::read_dir(&fuzz_targets_dir).with_context(|| {
+            format!(
+                "failed to read directory entries of {}",
+                fuzz_targets_dir.display()
+            )
+        })? {
+            let path = entry?.path();
+            if path.extension() == Some(OsStr::new("rs")) {
+                if let Some(target) = path.file_stem().and_then(OsStr::to_str) {
+                    existing_targets.push(target.to_string());
+                }
+            }
+        }
+        existing_targets.sort();
+        for target in &existing_targets {
+            project.register_target(target)?;
+        }
+
+        if !existing_targets.contains(&init.target) {
+            project
+                .create_target_template(&init.target, init.arbitrary.as_deref())
+                .with_context(|| {
+                    format!(
+                        "could not create template file for target {:?}",
+                        init.target
+                    )
+                })?;
+        }
         Ok(project)
     }
 
-    pub fn list_targets(&self) {
+    pub fn list_targets(&self, detail: bool, json: bool) {
+        if json {
+            let count = |dir: PathBuf| {
+                count_entries(&dir).map_or_else(|| String::from("null"), |count| count.to_string())
+            };
+            let targets: Vec<String> = self
+                .targets
+                .iter()
+                .map(|bin| {
+                    let output = self.fuzz_dir().join("artifacts").join(bin);
+                    format!(
+                        "{{\"name\":{},\"path\":{},\"corpus_count\":{},\"crash_count\":{}}}",
+                        json_string(bin),
+                        json_string(&self.target_path(bin).to_string_lossy()),
+                        count(output.join("corpus")),
+                        count(output.join("crashes"))
+                    )
+                })
+                .collect();
+            println!("[{}]", targets.join(","));
+            return;
+        }
+
+        if !detail {
+            for bin in &self.targets {
+                println!("{}", bin);
+            }
+            return;
+        }
+
+        let count = |dir: PathBuf| {
+            count_entries(&dir).map_or_else(|| String::from("-"), |count| count.to_string())
+        };
+        let width = self
+            .targets
+            .iter()
+            .map(String::len)
+            .chain(iter::once("TARGET".len()))
+            .max()
+            .unwrap_or_default();
+        println!("{:<width$}  {:>8}  {:>8}", "TARGET", "CORPUS", "CRASHES");
         for bin in &self.targets {
-            println!("{}", bin);
+            // Don't use `artifacts_for`, listing should not create any directories
+            let output = self.fuzz_dir().join("artifacts").join(bin);
+            println!(
+                "{:<width$}  {:>8}  {:>8}",
+                bin,
+                count(output.join("corpus")),
+                count(output.join("crashes"))
+            );
         }
     }
 
@@ -104,12 +206,13 @@ impl FuzzProject {
         // Create corpus and artifact directories for the newly added target
         self.corpus_for(&add.target)?;
         self.artifacts_for(&add.target)?;
-        self.create_target_template(&add.target)
+        self.create_target_template(&add.target, add.arbitrary.as_deref())
             .with_context(|| format!("could not add target {:?}", add.target))
     }
 
-    /// Add a new fuzz target script with a given name
-    fn create_target_template(&self, target: &str) -> Result<()> {
+    /// Add a new fuzz target script with a given name, taking raw bytes or,
+    /// if `arbitrary` is given, a structured input of that type
+    fn create_target_template(&self, target: &str, arbitrary: Option<&str>) -> Result<()> {
         let target_path = self.target_path(target);
 
         // If the user manually created a fuzz project, but hasn't created any
@@ -124,15 +227,75 @@ impl FuzzProject {
             .create_new(true)
             .open(&target_path)
             .with_context(|| format!("could not create target script file at {:?}", target_path))?;
-        script.write_fmt(target_template!())?;
+        if let Some(ty) = arbitrary {
+            script.write_fmt(arbitrary_target_template!(ty))?;
+            self.enable_arbitrary_derive()?;
+        } else {
+            script.write_fmt(target_template!())?;
+        }
+
+        self.register_target(target)
+    }
 
+    /// Adds the `[[bin]]` section of the script of `target` to the manifest
+    fn register_target(&self, target: &str) -> Result<()> {
+        let fuzz_targets_dir = self.fuzz_targets_dir();
+        let dir_name = fuzz_targets_dir
+            .file_name()
+            .and_then(OsStr::to_str)
+            .unwrap_or(crate::FUZZ_TARGETS_DIR);
         let mut cargo = fs::OpenOptions::new()
             .append(true)
             .open(self.manifest_path())?;
-        Ok(cargo.write_fmt(toml_bin_template!(target))?)
+        Ok(cargo.write_fmt(toml_bin_template!(target, dir_name))?)
+    }
+
+    /// Enables the `arbitrary-derive` feature of `cargo-libafl-helper` in the fuzz manifest
+    fn enable_arbitrary_derive(&self) -> Result<()> {
+        let helper = self
+            .manifest()?
+            .get("dependencies")
+            .and_then(|deps| deps.get("cargo-libafl-helper"))
+            .cloned();
+        let enabled = helper
+            .as_ref()
+            .and_then(|helper| helper.get("features"))
+            .and_then(toml::Value::as_array)
+            .is_some_and(|features| {
+                features
+                    .iter()
+                    .any(|feature| feature.as_str() == Some("arbitrary-derive"))
+            });
+        if enabled {
+            return Ok(());
+        }
+
+        // Only the plain `cargo-libafl-helper = "<version>"` form is rewritten,
+        // anything more elaborate is left to the user.
+        let manifest_path = self.manifest_path();
+        let manifest = fs::read_to_string(&manifest_path)?;
+        if let Some(toml::Value::String(version)) = helper {
+            let plain = format!("cargo-libafl-helper = \"{}\"", version);
+            if manifest.contains(&plain) {
+                let with_derive = format!(
+                    "cargo-libafl-helper = {{ version = \"{}\", features = [\"arbitrary-derive\"] }}",
+                    version
+                );
+                fs::write(&manifest_path, manifest.replacen(&plain, &with_derive, 1))
+                    .with_context(|| format!("failed to write to {}", manifest_path.display()))?;
+                return Ok(());
+            }
+        }
+
+        eprintln!(
+            "warning: could not enable the `arbitrary-derive` feature of `cargo-libafl-helper` \
+             in {}, please enable it manually",
+            manifest_path.display()
+        );
+        Ok(())
     }
 
-    fn cargo(&self, subcommand: &str, build: &BuildOptions) -> Command {
+    fn cargo(&self, subcommand: &str, build: &BuildOptions) -> Result<Command> {
         let mut cmd = Command::new("cargo");
         cmd.arg(subcommand)
             .arg("--manifest-path")
@@ -140,8 +303,11 @@ impl FuzzProject {
             // --target=<TARGET> won't pass rustflags to build scripts
             .arg("--target")
             .arg(&build.triple);
-        // we default to release mode unless debug mode is explicitly requested
-        if !build.dev {
+        // we default to release mode unless debug mode or another profile is
+        // explicitly requested
+        if let Some(ref profile) = build.profile {
+            cmd.arg("--profile").arg(profile);
+        } else if !build.dev {
             cmd.arg("--release");
         }
         if build.verbose {
@@ -159,7 +325,7 @@ impl FuzzProject {
         for flag in &build.unstable_flags {
             cmd.arg("-Z").arg(flag);
         }
-        if let Sanitizer::Memory = build.sanitizer {
+        if build.sanitizer.contains(&Sanitizer::Memory) {
             cmd.arg("-Z").arg("build-std");
         }
 
@@ -171,16 +337,27 @@ impl FuzzProject {
 
         // link the fuzzer runtime
         rustflags.push_str(" -L ");
-        rustflags.push_str(&common::runtime_dir().to_string_lossy());
+        rustflags.push_str(&common::runtime_dir(&build.triple, &build.rustflags).to_string_lossy());
         rustflags.push_str(" -lcargo_libafl_runtime");
 
         if !build.no_trace_compares {
             rustflags.push_str(" -Cllvm-args=-sanitizer-coverage-trace-compares");
         }
 
-        if !build.no_cfg_fuzzing {
+        // With `--no-cfg-fuzzing-deps`, the wrapper adds it to the fuzz crate and the fuzzed
+        // crate only, which it tells apart by their package names
+        if !build.no_cfg_fuzzing && !build.no_cfg_fuzzing_deps {
             rustflags.push_str(" --cfg fuzzing");
         }
+        if build.no_cfg_fuzzing_deps {
+            if env::var_os("RUSTC_WRAPPER").is_some_and(|wrapper| !wrapper.is_empty()) {
+                bail!("--no-cfg-fuzzing-deps cannot be combined with a RUSTC_WRAPPER");
+            }
+            let exe = env::current_exe()
+                .context("failed to find the cargo-libafl executable for --no-cfg-fuzzing-deps")?;
+            cmd.env("RUSTC_WRAPPER", exe)
+                .env(utils::CFG_FUZZING_WRAPPER_ENV, self.root_project_name()?);
+        }
 
         if !build.strip_dead_code {
             rustflags.push_str(" -Clink-dead-code");
@@ -190,19 +367,38 @@ impl FuzzProject {
             rustflags.push_str(" -Zinstrument-coverage");
         }
 
-        match build.sanitizer {
-            Sanitizer::None => {}
-            Sanitizer::Memory => {
-                // Memory sanitizer requires more flags to function than others:
-                // https://doc.rust-lang.org/unstable-book/compiler-flags/sanitizer.html#memorysanitizer
-                rustflags.push_str(" -Zsanitizer=memory -Zsanitizer-memory-track-origins");
-            }
-            _ => write!(rustflags, " -Zsanitizer={}", build.sanitizer).unwrap(),
+        let rustc_sanitizers: Vec<String> = build
+            .sanitizer
+            .iter()
+            .filter(|sanitizer| !matches!(sanitizer, Sanitizer::None | Sanitizer::Undefined))
+            .map(ToString::to_string)
+            .collect();
+        if !rustc_sanitizers.is_empty() {
+            write!(rustflags, " -Zsanitizer={}", rustc_sanitizers.join(",")).unwrap();
+        }
+        if build.sanitizer.contains(&Sanitizer::Memory) {
+            // Memory sanitizer requires more flags to function than others:
+            // https://doc.rust-lang.org/unstable-book/compiler-flags/sanitizer.html#memorysanitizer
+            rustflags.push_str(" -Zsanitizer-memory-track-origins");
+        }
+        if build.sanitizer.contains(&Sanitizer::HwAddress) {
+            // Globals have to be tagged as well:
+            // https://doc.rust-lang.org/unstable-book/compiler-flags/sanitizer.html#hwaddresssanitizer
+            rustflags.push_str(" -Ctarget-feature=+tagged-globals");
         }
         if build.triple.contains("-linux-") {
             rustflags.push_str(" -Cllvm-args=-sanitizer-coverage-stack-depth");
         }
-        if !build.release || build.debug_assertions {
+        if build.debug_info {
+            rustflags.push_str(" -Cdebuginfo=2");
+        }
+        // A panic can't unwind out of the `extern "C"` entry of the harness, so it aborts and
+        // the runtime sees a crash with either strategy
+        if let Some(panic) = build.panic {
+            write!(rustflags, " -Cpanic={}", panic).unwrap();
+        }
+        // a custom profile decides about debug assertions on its own
+        if (!build.release && build.profile.is_none()) || build.debug_assertions {
             rustflags.push_str(" -Cdebug-assertions");
         }
         if build.triple.contains("-msvc") {
@@ -226,43 +422,105 @@ impl FuzzProject {
             rustflags.push(' ');
             rustflags.push_str(&other_flags);
         }
+        // flags from the command line come last so they can override everything else
+        for flag in &build.rustflags {
+            rustflags.push(' ');
+            rustflags.push_str(flag);
+        }
         cmd.env("RUSTFLAGS", rustflags);
 
         // For asan and tsan we have default options. Merge them to the given
         // options, so users can still provide their own options to e.g. disable
         // the leak sanitizer.  Options are colon-separated.
-        match build.sanitizer {
-            Sanitizer::Address => {
-                let mut asan_opts = env::var("ASAN_OPTIONS").unwrap_or_default();
-                if !asan_opts.is_empty() {
-                    asan_opts.push(':');
+        for sanitizer in &build.sanitizer {
+            match sanitizer {
+                Sanitizer::Address => {
+                    let mut asan_opts = env::var("ASAN_OPTIONS").unwrap_or_default();
+                    if !asan_opts.is_empty() {
+                        asan_opts.push(':');
+                    }
+                    asan_opts.push_str("detect_odr_violation=0");
+                    // ASAN detects leaks by default, only override it when asked to
+                    if !build.detect_leaks {
+                        asan_opts.push_str(":detect_leaks=0");
+                    }
+                    cmd.env("ASAN_OPTIONS", asan_opts);
                 }
-                asan_opts.push_str("detect_odr_violation=0");
-                cmd.env("ASAN_OPTIONS", asan_opts);
-            }
 
-            Sanitizer::Thread => {
-                let mut tsan_opts = env::var("TSAN_OPTIONS").unwrap_or_default();
-                if !tsan_opts.is_empty() {
-                    tsan_opts.push(':');
+                Sanitizer::Thread => {
+                    let mut tsan_opts = env::var("TSAN_OPTIONS").unwrap_or_default();
+                    if !tsan_opts.is_empty() {
+                        tsan_opts.push(':');
+                    }
+                    tsan_opts.push_str("report_signal_unsafe=0");
+                    cmd.env("TSAN_OPTIONS", tsan_opts);
                 }
-                tsan_opts.push_str("report_signal_unsafe=0");
-                cmd.env("TSAN_OPTIONS", tsan_opts);
+
+                // rustc has no UBSan, but C/C++ dependencies built by `cc` can use it
+                Sanitizer::Undefined => {
+                    for var in ["CFLAGS", "CXXFLAGS"] {
+                        let mut flags = env::var(var).unwrap_or_default();
+                        if !flags.is_empty() {
+                            flags.push(' ');
+                        }
+                        flags.push_str("-fsanitize=undefined");
+                        cmd.env(var, flags);
+                    }
+                }
+
+                _ => {}
             }
+        }
 
-            _ => {}
+        // Inherited by the fuzz target that `cargo run` starts, and set last to override the above
+        cmd.envs(build.env.iter().map(|(key, value)| (key, value)));
+
+        Ok(cmd)
+    }
+
+    /// Returns the target dir to pass to cargo, resolved as `--target-dir`, else
+    /// `CARGO_TARGET_DIR`, else the `target` dir of the fuzz crate.
+    /// The same dir is used to find the built binaries, see `binary_path`.
+    fn target_dir(&self, build: &options::BuildOptions) -> Result<PathBuf> {
+        if let Some(target_dir) = &build.target_dir {
+            return Ok(PathBuf::from(target_dir));
+        }
+        let base = env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.fuzz_dir().join("target"));
+        if build.coverage {
+            // To ensure that fuzzing and coverage-output generation can run in parallel, we
+            // produce a separate binary for the coverage command.
+            Ok(base.join(&build.triple).join("coverage"))
+        } else {
+            Ok(base)
         }
+    }
 
-        cmd
+    /// Returns the path of the fuzz target binary produced by `exec_build`
+    fn binary_path(&self, build: &options::BuildOptions, fuzz_target: &str) -> Result<PathBuf> {
+        let target_dir = self.target_dir(build)?;
+        // cargo keeps the historical directory names for the built-in profiles
+        let profile = match build.profile.as_deref() {
+            Some("dev" | "test") => "debug",
+            Some("release" | "bench") => "release",
+            Some(profile) => profile,
+            None if build.dev => "debug",
+            None => "release",
+        };
+        Ok(target_dir
+            .join(&build.triple)
+            .join(profile)
+            .join(fuzz_target))
     }
 
     fn cargo_run(&self, build: &options::BuildOptions, fuzz_target: &str) -> Result<Command> {
-        let mut cmd = self.cargo("run", build);
+        let build = &self.target_build_options(build, fuzz_target)?;
+        ensure_runtime(&build.triple, &build.rustflags)?;
+        let mut cmd = self.cargo("run", build)?;
         cmd.arg("--bin").arg(fuzz_target);
 
-        if let Some(target_dir) = &build.target_dir {
-            cmd.arg("--target-dir").arg(target_dir);
-        }
+        cmd.arg("--target-dir").arg(self.target_dir(build)?);
 
         let artifact_arg = ffi::OsString::from(self.artifacts_for(fuzz_target)?);
         cmd.arg("--").arg("--output").arg(artifact_arg);
@@ -270,12 +528,18 @@ impl FuzzProject {
         Ok(cmd)
     }
 
-    pub fn exec_build(
+    fn build_command(
         &self,
         build: &options::BuildOptions,
         fuzz_target: Option<&str>,
-    ) -> Result<()> {
-        let mut cmd = self.cargo("build", build);
+    ) -> Result<Command> {
+        check_sanitizers(build)?;
+
+        let build = &match fuzz_target {
+            Some(fuzz_target) => self.target_build_options(build, fuzz_target)?,
+            None => build.clone(),
+        };
+        let mut cmd = self.cargo("build", build)?;
 
         if let Some(fuzz_target) = fuzz_target {
             cmd.arg("--bin").arg(fuzz_target);
@@ -283,25 +547,145 @@ impl FuzzProject {
             cmd.arg("--bins");
         }
 
-        if let Some(target_dir) = &build.target_dir {
-            cmd.arg("--target-dir").arg(target_dir);
-        } else if build.coverage {
-            // To ensure that fuzzing and coverage-output generation can run in parallel, we
-            // produce a separate binary for the coverage command.
-            let target_dir = env::current_dir()?
-                .join("target")
-                .join(default_target())
-                .join("coverage");
-            cmd.arg("--target-dir").arg(target_dir);
+        cmd.arg("--target-dir").arg(self.target_dir(build)?);
+
+        Ok(cmd)
+    }
+
+    /// Returns the `cargo build` commands that build `fuzz_target`, or all targets.
+    /// Targets with their own features in the manifest can't share a build, so then all
+    /// targets are built one by one.
+    fn build_commands(
+        &self,
+        build: &options::BuildOptions,
+        fuzz_target: Option<&str>,
+    ) -> Result<Vec<Command>> {
+        if fuzz_target.is_none() {
+            let mut per_target = false;
+            for target in &self.targets {
+                per_target |= !self.target_features(target)?.is_empty();
+            }
+            if per_target {
+                return self
+                    .targets
+                    .iter()
+                    .map(|target| self.build_command(build, Some(target.as_str())))
+                    .collect();
+            }
         }
+        Ok(vec![self.build_command(build, fuzz_target)?])
+    }
 
-        let status = cmd
-            .status()
-            .with_context(|| format!("failed to execute: {:?}", cmd))?;
-        if !status.success() {
-            bail!("failed to build fuzz script: {:?}", cmd);
+    /// Prints the `cargo build` command line `exec_build` would run, with the environment
+    /// variables it sets, in a form that can be pasted into a shell
+    pub fn print_build_command(
+        &self,
+        build: &options::BuildOptions,
+        fuzz_target: Option<&str>,
+    ) -> Result<()> {
+        for cmd in self.build_commands(build, fuzz_target)? {
+            let mut line = Vec::new();
+            for (key, value) in cmd.get_envs() {
+                if let Some(value) = value {
+                    line.push(format!("{}={}", key.to_string_lossy(), shell_quote(value)));
+                }
+            }
+            line.push(shell_quote(cmd.get_program()));
+            line.extend(cmd.get_args().map(shell_quote));
+            println!("{}", line.join(" "));
         }
+        Ok(())
+    }
 
+    pub fn exec_build(
+        &self,
+        build: &options::BuildOptions,
+        fuzz_target: Option<&str>,
+    ) -> Result<()> {
+        ensure_runtime(&build.triple, &build.rustflags)?;
+        match fuzz_target {
+            Some(target) => self.run_pre_build(build, target)?,
+            None => {
+                for target in &self.targets {
+                    self.run_pre_build(build, target)?;
+                }
+            }
+        }
+        for mut cmd in self.build_commands(build, fuzz_target)? {
+            let status = cmd
+                .status()
+                .with_context(|| format!("failed to execute: {:?}", cmd))?;
+            if !status.success() {
+                bail!("failed to build fuzz script: {:?}", cmd);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Builds all targets one by one, going on after failures, and prints which ones failed
+    /// with the errors from their build output
+    pub fn exec_build_keep_going(&self, build: &options::BuildOptions) -> Result<()> {
+        ensure_runtime(&build.triple, &build.rustflags)?;
+        let mut outcomes = Vec::new();
+        for (i, target) in self.targets.iter().enumerate() {
+            eprintln!(
+                "Building target `{}` ({}/{})",
+                target,
+                i + 1,
+                self.targets.len()
+            );
+            let errors = match self.run_pre_build(build, target) {
+                Ok(()) => {
+                    let mut cmd = self.build_command(build, Some(target.as_str()))?;
+                    let output = cmd
+                        .stderr(Stdio::piped())
+                        .output()
+                        .with_context(|| format!("failed to execute: {:?}", cmd))?;
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    eprint!("{}", stderr);
+                    if output.status.success() {
+                        None
+                    } else {
+                        Some(build_errors(&stderr))
+                    }
+                }
+                Err(e) => Some(vec![e.to_string()]),
+            };
+            outcomes.push((target, errors));
+        }
+
+        let width = self
+            .targets
+            .iter()
+            .map(|target| target.len())
+            .max()
+            .unwrap_or(0);
+        eprintln!("\nSummary:\n");
+        for (target, errors) in &outcomes {
+            match errors {
+                None => eprintln!("\t{:width$}  built", target, width = width),
+                Some(errors) => {
+                    eprintln!("\t{:width$}  failed", target, width = width);
+                    for error in errors {
+                        eprintln!("\t{:width$}    {}", "", error, width = width);
+                    }
+                }
+            }
+        }
+        eprintln!();
+
+        let failed = outcomes
+            .iter()
+            .filter(|(_, errors)| errors.is_some())
+            .count();
+        if failed > 0 {
+            bail!(
+                "{} of {} fuzz targets failed to build",
+                failed,
+                outcomes.len()
+            );
+        }
         Ok(())
     }
 
@@ -312,32 +696,45 @@ impl FuzzProject {
     ) -> Result<HashSet<PathBuf>> {
         let mut artifacts = HashSet::new();
 
+        // The runtime saves the solutions as `crashes/crash-<sha1>` and `timeouts/timeout-<sha1>`,
+        // and their minimized versions of `-- --auto-tmin` as `minimized/crash-<sha1>`,
+        // below `latest` with `-- --timestamp-output --latest-symlink`
         let artifacts_dir = self.artifacts_for(target)?;
+        let latest_dir = artifacts_dir.join("latest");
+        let solutions_dirs = [
+            artifacts_dir.clone(),
+            artifacts_dir.join("crashes"),
+            artifacts_dir.join("timeouts"),
+            artifacts_dir.join("minimized"),
+            latest_dir.join("crashes"),
+            latest_dir.join("timeouts"),
+            latest_dir.join("minimized"),
+        ];
+
+        for dir in solutions_dirs.iter().filter(|dir| dir.is_dir()) {
+            for entry in fs::read_dir(dir)
+                .with_context(|| format!("failed to read directory entries of {}", dir.display()))?
+            {
+                let entry = entry.with_context(|| {
+                    format!("failed to read directory entry inside {}", dir.display())
+                })?;
 
-        for entry in fs::read_dir(&artifacts_dir).with_context(|| {
-            format!(
-                "failed to read directory entries of {}",
-                artifacts_dir.display()
-            )
-        })? {
-            let entry = entry.with_context(|| {
-                format!(
-                    "failed to read directory entry inside {}",
-                    artifacts_dir.display()
-                )
-            })?;
+                // Skip the metadata files stored next to the solutions
+                if entry.file_name().to_string_lossy().starts_with('.') {
+                    continue;
+                }
+                let metadata = entry
+                    .metadata()
+                    .context("failed to read artifact metadata")?;
+                let modified = metadata
+                    .modified()
+                    .context("failed to get artifact modification time")?;
+                if !metadata.is_file() || modified <= *since {
+                    continue;
+                }
 
-            let metadata = entry
-                .metadata()
-                .context("failed to read artifact metadata")?;
-            let modified = metadata
-                .modified()
-                .context("failed to get artifact modification time")?;
-            if !metadata.is_file() || modified <= *since {
-                continue;
+                artifacts.insert(entry.path());
             }
-
-            artifacts.insert(entry.path());
         }
 
         Ok(artifacts)
@@ -353,8 +750,8 @@ impl FuzzProject {
 
         let mut cmd = self.cargo_run(build, target)?;
         cmd.stdin(Stdio::null());
-        cmd.env("RUST_LIBFUZZER_DEBUG_PATH", &debug_output.path());
-        cmd.arg(&artifact);
+        cmd.env("RUST_LIBFUZZER_DEBUG_PATH", debug_output.path());
+        cmd.arg(artifact);
 
         let output = cmd
             .output()
@@ -393,18 +790,71 @@ impl FuzzProject {
             );
         }
 
-        let debug = self
-            .run_fuzz_target_debug_formatter(&debugfmt.build, &debugfmt.target, &debugfmt.input)
+        if !debugfmt.input.is_dir() {
+            let debug = self
+                .run_fuzz_target_debug_formatter(&debugfmt.build, &debugfmt.target, &debugfmt.input)
+                .with_context(|| {
+                    format!(
+                        "failed to run `cargo fuzz fmt` on input: {}",
+                        debugfmt.input.display()
+                    )
+                })?;
+
+            eprintln!("\nOutput of `std::fmt::Debug`:\n");
+            for l in debug.lines() {
+                eprintln!("{}", l);
+            }
+            if debugfmt.hexdump {
+                let bytes = fs::read(&debugfmt.input)
+                    .with_context(|| format!("failed to read {}", debugfmt.input.display()))?;
+                eprintln!("\nHexdump of the input:\n");
+                for l in hexdump(&bytes).lines() {
+                    eprintln!("{}", l);
+                }
+            }
+            return Ok(());
+        }
+
+        let mut inputs = fs::read_dir(&debugfmt.input)
             .with_context(|| {
                 format!(
-                    "failed to run `cargo fuzz fmt` on input: {}",
+                    "failed to read directory entries of {}",
                     debugfmt.input.display()
                 )
-            })?;
+            })?
+            .flatten()
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+        inputs.sort();
+        if let Some(limit) = debugfmt.limit {
+            inputs.truncate(limit);
+        }
 
-        eprintln!("\nOutput of `std::fmt::Debug`:\n");
-        for l in debug.lines() {
-            eprintln!("{}", l);
+        // Keep going on failures, a single odd input should not hide the others
+        for input in &inputs {
+            eprintln!("\n{:─<80}", "");
+            eprintln!("\n{}:\n", input.display());
+            match self.run_fuzz_target_debug_formatter(&debugfmt.build, &debugfmt.target, input) {
+                Ok(debug) => {
+                    for l in debug.lines() {
+                        eprintln!("\t{}", l);
+                    }
+                }
+                Err(err) => eprintln!("\tfailed to debug print: {:#}", err),
+            }
+            if debugfmt.hexdump {
+                match fs::read(input) {
+                    Ok(bytes) => {
+                        eprintln!();
+                        for l in hexdump(&bytes).lines() {
+                            eprintln!("\t{}", l);
+                        }
+                    }
+                    Err(err) => eprintln!("\tfailed to read: {}", err),
+                }
+            }
         }
 
         Ok(())
@@ -412,17 +862,153 @@ impl FuzzProject {
 
     /// Fuzz a given fuzz target
     pub fn exec_fuzz(&self, run: &options::Run) -> Result<()> {
-        self.exec_build(&run.build, Some(&run.target))?;
-        let mut cmd = self.cargo_run(&run.build, &run.target)?;
+        let run = &options::Run {
+            args: translate_libfuzzer_args(&run.args)?,
+            ..run.clone()
+        };
+
+        // Leading positional arguments that name fuzz targets are more targets to run in turn
+        let extra_targets = run
+            .corpus
+            .iter()
+            .take_while(|arg| self.targets.contains(arg))
+            .count();
+        let (extra_targets, corpus) = run.corpus.split_at(extra_targets);
+
+        if run.max_total_time.is_some() && has_runtime_flag(&run.args, "--max-total-time") {
+            bail!("`--max-total-time` was given both before and after `--`");
+        }
+
+        if extra_targets.is_empty() {
+            if run.reproduce && corpus.is_empty() {
+                bail!("`--reproduce` needs at least one corpus directory or artifact file");
+            }
+            let corpus: Vec<PathBuf> = corpus.iter().map(PathBuf::from).collect();
+            let status = self.fuzz_target(run, &run.target, &corpus, run.max_total_time)?;
+            if status.success() {
+                return Ok(());
+            }
+            if status.code() == Some(SOLUTION_EXIT_CODE) {
+                // Pass it on, so that scripts can tell found crashes apart from other failures
+                std::process::exit(SOLUTION_EXIT_CODE);
+            }
+            bail!("Fuzz target exited with {}", status)
+        }
+
+        if !corpus.is_empty() {
+            bail!(
+                "corpus paths can not be given for several fuzz targets, \
+                 each one uses its own `corpus/<target>` directory"
+            );
+        }
+
+        let targets: Vec<&str> = iter::once(run.target.as_str())
+            .chain(extra_targets.iter().map(String::as_str))
+            .collect();
+        // Split the shared budget evenly, but give every target at least a second
+        let budget = run
+            .max_total_time
+            .map(|secs| (secs / targets.len() as u64).max(1));
+        if budget.is_none() {
+            // Without a budget, the first target would keep the others from ever running
+            for target in &targets {
+                let defaults = self.default_runtime_args(target, &run.args)?;
+                if !has_runtime_flag(&run.args, "--max-total-time")
+                    && !has_runtime_flag(&defaults, "--max-total-time")
+                {
+                    bail!(
+                        "fuzz target `{}` has no time budget, pass `--max-total-time` to share \
+                         one among all targets, or set one per target in the manifest",
+                        target
+                    );
+                }
+            }
+        }
+
+        let mut outcomes = Vec::new();
+        for (i, target) in targets.iter().enumerate() {
+            eprintln!("Fuzzing target `{}` ({}/{})", target, i + 1, targets.len());
+            let corpus = [self.corpus_for(target)?];
+            let status = self.fuzz_target(run, target, &corpus, budget)?;
+            outcomes.push((*target, status));
+        }
+
+        let width = targets.iter().map(|target| target.len()).max().unwrap_or(0);
+        eprintln!("\nSummary:\n");
+        for (target, status) in &outcomes {
+            let outcome = if status.success() {
+                String::from("no solutions")
+            } else if status.code() == Some(SOLUTION_EXIT_CODE) {
+                format!(
+                    "solutions found in {}",
+                    self.artifacts_for(target)?.display()
+                )
+            } else {
+                format!("failed, {}", status)
+            };
+            eprintln!("\t{:width$}  {}", target, outcome, width = width);
+        }
+        eprintln!();
+
+        let failed = outcomes
+            .iter()
+            .filter(|(_, status)| !status.success() && status.code() != Some(SOLUTION_EXIT_CODE))
+            .count();
+        if failed > 0 {
+            bail!("{} of {} fuzz targets failed", failed, outcomes.len());
+        }
+        if outcomes
+            .iter()
+            .any(|(_, status)| status.code() == Some(SOLUTION_EXIT_CODE))
+        {
+            std::process::exit(SOLUTION_EXIT_CODE);
+        }
+        Ok(())
+    }
 
-        for arg in &run.args {
+    /// Builds and fuzzes a single target, printing any new artifacts it left behind
+    fn fuzz_target(
+        &self,
+        run: &options::Run,
+        target: &str,
+        corpus: &[PathBuf],
+        max_total_time: Option<u64>,
+    ) -> Result<ExitStatus> {
+        self.exec_build(&run.build, Some(target))?;
+        let mut cmd = self.cargo_run(&run.build, target)?;
+
+        if run.reproduce {
+            cmd.arg("--reproduce");
+        }
+
+        // the runtime rejects repeated flags, so don't add it if it was passed through already
+        if run.no_unicode && !run.args.iter().any(|arg| arg == "--disable-unicode") {
+            cmd.arg("--disable-unicode");
+        }
+
+        let mut args = run.args.clone();
+        if let Some(max_total_time) = max_total_time {
+            args.push(format!("--max-total-time={}", max_total_time));
+        }
+
+        for arg in self.default_runtime_args(target, &args)? {
+            cmd.arg(arg);
+        }
+
+        for arg in &args {
             cmd.arg(arg);
         }
 
-        if !run.corpus.is_empty() {
-            for corpus in &run.corpus {
+        if !corpus.is_empty() {
+            for path in corpus {
+                if !run.reproduce && !path.exists() {
+                    fs::create_dir_all(path).with_context(|| {
+                        format!("could not make a corpus directory at {:?}", path)
+                    })?;
+                }
+                // the runtime tells seed directories and single seed files apart
                 cmd.arg("--input");
-                cmd.arg(corpus);
+                cmd.arg(path);
             }
         } /*else {
               cmd.arg(self.corpus_for(&run.target)?);
@@ -441,13 +1027,13 @@ impl FuzzProject {
             .wait()
             .with_context(|| format!("failed to wait on child process for command: {:?}", cmd))?;
         if status.success() {
-            return Ok(());
+            return Ok(status);
         }
 
         // Get and print the `Debug` formatting of any new artifacts, along with
         // tips about how to reproduce failures and/or minimize test cases.
 
-        let new_artifacts = self.get_artifacts_since(&run.target, &before_fuzzing)?;
+        let new_artifacts = self.get_artifacts_since(target, &before_fuzzing)?;
 
         for artifact in new_artifacts {
             // To make the artifact a little easier to read, strip the current
@@ -461,9 +1047,7 @@ impl FuzzProject {
             // likely just means that we're dealing with a fuzz target that uses
             // an older version of the libfuzzer crate, and doesn't support
             // `RUST_LIBFUZZER_DEBUG_PATH`.
-            if let Ok(debug) =
-                self.run_fuzz_target_debug_formatter(&run.build, &run.target, artifact)
-            {
+            if let Ok(debug) = self.run_fuzz_target_debug_formatter(&run.build, target, artifact) {
                 eprintln!("Output of `std::fmt::Debug`:\n");
                 for l in debug.lines() {
                     eprintln!("\t{}", l);
@@ -478,24 +1062,251 @@ impl FuzzProject {
             };
 
             eprintln!(
-                "Reproduce with:\n\n\tcargo fuzz run{fuzz_dir}{options} {target} {artifact}\n",
+                "Reproduce with:\n\n\tcargo libafl run --reproduce{fuzz_dir}{options} {target} {artifact}\n",
                 fuzz_dir = &fuzz_dir,
                 options = &run.build,
-                target = &run.target,
+                target = target,
                 artifact = artifact.display()
             );
         }
 
         eprintln!("{:─<80}\n", "");
-        bail!("Fuzz target exited with {}", status)
+        Ok(status)
+    }
+
+    /// Prints the count, the total, minimum, median and maximum size of the inputs in the
+    /// corpus of a target, and a histogram of their sizes in power-of-two buckets
+    pub fn exec_stats(&self, stats: &options::Stats) -> Result<()> {
+        const BAR_WIDTH: usize = 40;
+
+        // Don't use `corpus_for`, the stats should not create any directories
+        let corpus = match &stats.corpus {
+            Some(corpus) => corpus.clone(),
+            None => self.fuzz_dir().join("corpus").join(&stats.target),
+        };
+        let entries = fs::read_dir(&corpus)
+            .with_context(|| format!("failed to read corpus directory {}", corpus.display()))?;
+        let mut sizes = Vec::new();
+        for entry in entries {
+            let entry = entry.with_context(|| {
+                format!("failed to read directory entry inside {}", corpus.display())
+            })?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let metadata = entry.metadata().context("failed to read input metadata")?;
+            if metadata.is_file() {
+                sizes.push(metadata.len());
+            }
+        }
+        sizes.sort_unstable();
+
+        let total: u64 = sizes.iter().sum();
+        let median = match sizes.len() {
+            0 => None,
+            len if len % 2 == 0 => Some((sizes[len / 2 - 1] + sizes[len / 2]) / 2),
+            len => Some(sizes[len / 2]),
+        };
+        // Bucket 0 holds the empty inputs, bucket `i` the sizes from `2^(i-1)` to `2^i - 1`
+        let bucket = |size: u64| (u64::BITS - size.leading_zeros()) as usize;
+        let mut histogram = vec![0usize; sizes.last().map_or(0, |&max| bucket(max) + 1)];
+        for &size in &sizes {
+            histogram[bucket(size)] += 1;
+        }
+        let bounds = |i: usize| match i {
+            0 => (0, 0),
+            i => (1u64 << (i - 1), (1u64 << i) - 1),
+        };
+
+        if stats.json {
+            let optional = |value: Option<u64>| {
+                value.map_or_else(|| String::from("null"), |value| value.to_string())
+            };
+            let buckets: Vec<String> = histogram
+                .iter()
+                .enumerate()
+                .map(|(i, count)| {
+                    let (min, max) = bounds(i);
+                    format!("{{\"min\":{},\"max\":{},\"count\":{}}}", min, max, count)
+                })
+                .collect();
+            println!(
+                "{{\"corpus\":{},\"count\":{},\"total_bytes\":{},\"min\":{},\"median\":{},\"max\":{},\"histogram\":[{}]}}",
+                json_string(&corpus.to_string_lossy()),
+                sizes.len(),
+                total,
+                optional(sizes.first().copied()),
+                optional(median),
+                optional(sizes.last().copied()),
+                buckets.join(",")
+            );
+            return Ok(());
+        }
+
+        println!("Corpus:  {}", corpus.display());
+        println!("Inputs:  {}", sizes.len());
+        println!("Total:   {} bytes", total);
+        if let (Some(min), Some(median), Some(max)) = (sizes.first(), median, sizes.last()) {
+            println!("Min:     {} bytes", min);
+            println!("Median:  {} bytes", median);
+            println!("Max:     {} bytes", max);
+        }
+        if histogram.is_empty() {
+            return Ok(());
+        }
+
+        let most = histogram.iter().copied().max().unwrap_or_default();
+        let labels: Vec<String> = (0..histogram.len())
+            .map(|i| {
+                let (min, max) = bounds(i);
+                format!("{}-{} B", min, max)
+            })
+            .collect();
+        let width = labels.iter().map(String::len).max().unwrap_or_default();
+        println!();
+        for (label, &count) in labels.iter().zip(&histogram) {
+            // Round up, so that every non-empty bucket gets a mark
+            let bar = (count * BAR_WIDTH).div_ceil(most);
+            println!(
+                "{:>width$}  {:<bar_width$}  {}",
+                label,
+                "#".repeat(bar),
+                count,
+                width = width,
+                bar_width = BAR_WIDTH
+            );
+        }
+        Ok(())
+    }
+
+    /// Replay every input of a corpus once, failing if any of them crashes or times out
+    pub fn exec_verify(&self, verify: &options::Verify) -> Result<()> {
+        let corpus = match &verify.corpus {
+            Some(corpus) => corpus.clone(),
+            None => self.corpus_for(&verify.target)?,
+        };
+        if !corpus.is_dir() {
+            bail!("Corpus directory does not exist: {}", corpus.display());
+        }
+
+        self.exec_build(&verify.build, Some(&verify.target))?;
+        let mut cmd = self.cargo_run(&verify.build, &verify.target)?;
+        cmd.arg("--reproduce").arg("--input").arg(&corpus);
+
+        for arg in &verify.args {
+            cmd.arg(arg);
+        }
+
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to run command: {:?}", cmd))?;
+        if !status.success() {
+            bail!(
+                "corpus {} does not replay cleanly, see the failing inputs above",
+                corpus.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Minimize a crashing input, preserving the crash
+    pub fn exec_tmin(&self, tmin: &options::Tmin) -> Result<()> {
+        if !tmin.input.exists() {
+            bail!("Input test case does not exist: {}", tmin.input.display());
+        }
+
+        self.exec_build(&tmin.build, Some(&tmin.target))?;
+        let mut cmd = self.cargo_run(&tmin.build, &tmin.target)?;
+
+        let output = tmin.output.clone().unwrap_or_else(|| {
+            let mut output = tmin.input.clone().into_os_string();
+            output.push(".min");
+            PathBuf::from(output)
+        });
+
+        cmd.arg("--tmin")
+            .arg(&tmin.input)
+            .arg("--tmin-output")
+            .arg(&output)
+            .arg("--tmin-runs")
+            .arg(tmin.runs.to_string());
+
+        for arg in &tmin.args {
+            cmd.arg(arg);
+        }
+
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to run command: {:?}", cmd))?;
+        if !status.success() {
+            bail!(
+                "failed to minimize {}: fuzz target exited with {}",
+                tmin.input.display(),
+                status
+            );
+        }
+
+        eprintln!("Minimized input saved in {}", output.display());
+        Ok(())
     }
 
     /// Produce coverage information for a given corpus
     pub fn exec_coverage(self, coverage: &options::Coverage) -> Result<()> {
+        let (coverage_out_raw_dir, coverage_out_file) = self.coverage_for(&coverage.target)?;
+        let binary = self.binary_path(&coverage.build, &coverage.target)?;
+
+        // The raw coverage data, e.g. of corpus shards run elsewhere, only needs to be merged
+        let profraw_dir = if let Some(merge_only) = &coverage.merge_only {
+            if !merge_only.is_dir() {
+                bail!(
+                    "Raw coverage directory does not exist: {}",
+                    merge_only.display()
+                );
+            }
+            if !binary.is_file() {
+                bail!(
+                    "The coverage build of {} is missing at {}, the report needs it. \
+                     Run `cargo libafl coverage` without `--merge-only` once to build it.",
+                    coverage.target,
+                    binary.display()
+                );
+            }
+            merge_only.clone()
+        } else {
+            self.generate_coverage_data(coverage, &coverage_out_raw_dir)?;
+            coverage_out_raw_dir
+        };
+
+        Self::merge_coverage(&profraw_dir, &coverage_out_file)?;
+
+        let report_dir = coverage_out_file
+            .parent()
+            .context("coverage data file has no parent directory")?;
+        Self::export_coverage(
+            coverage.output_format,
+            &binary,
+            &coverage_out_file,
+            report_dir,
+        )?;
+
+        if coverage.summary {
+            self.print_coverage_summary(&binary, &coverage_out_file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the fuzz target with coverage and runs it on each input, writing a raw coverage
+    /// file per input to `coverage_out_raw_dir`
+    fn generate_coverage_data(
+        &self,
+        coverage: &options::Coverage,
+        coverage_out_raw_dir: &Path,
+    ) -> Result<()> {
         // Build project with source-based coverage generation enabled.
         self.exec_build(&coverage.build, Some(&coverage.target))?;
 
-        // Retrieve corpus directories.
+        // Retrieve corpus directories and single input files.
         let corpora = if coverage.corpus.is_empty() {
             vec![self.corpus_for(&coverage.target)?]
         } else {
@@ -507,14 +1318,28 @@ impl FuzzProject {
         };
 
         // Collect the (non-directory) readable input files from the corpora.
-        let files_and_dirs = corpora.iter().flat_map(fs::read_dir).flatten().flatten();
-        let mut readable_input_files = files_and_dirs
-            .filter(|file| match file.file_type() {
-                Ok(ft) => ft.is_file(),
-                _ => false,
-            })
-            .peekable();
-        if readable_input_files.peek().is_none() {
+        let mut readable_input_files = Vec::new();
+        for corpus in &corpora {
+            if corpus.is_file() {
+                readable_input_files.push(corpus.clone());
+            } else if corpus.is_dir() {
+                let entries = fs::read_dir(corpus).with_context(|| {
+                    format!("failed to read directory entries of {}", corpus.display())
+                })?;
+                readable_input_files.extend(
+                    entries
+                        .flatten()
+                        .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_file()))
+                        .map(|entry| entry.path()),
+                );
+            } else {
+                bail!(
+                    "corpus path {} is neither a file nor a directory",
+                    corpus.display()
+                );
+            }
+        }
+        if readable_input_files.is_empty() {
             bail!(
                 "The corpus does not contain program-input files. \
                  Coverage information requires existing input files. \
@@ -523,12 +1348,10 @@ impl FuzzProject {
             )
         }
 
-        let (coverage_out_raw_dir, coverage_out_file) = self.coverage_for(&coverage.target)?;
-
         // Generating individual coverage data for all files in corpora.
         for input_file in readable_input_files {
             let (mut cmd, file_name) =
-                self.create_coverage_cmd(coverage, &coverage_out_raw_dir, &input_file.path())?;
+                self.create_coverage_cmd(coverage, coverage_out_raw_dir, &input_file)?;
             eprintln!("Generating coverage data for {:?}", file_name);
             let status = cmd
                 .status()
@@ -543,8 +1366,60 @@ impl FuzzProject {
             }
         }
 
-        Self::merge_coverage(&coverage_out_raw_dir, &coverage_out_file)?;
+        Ok(())
+    }
+
+    /// Prints the line coverage of each function of the fuzzed crate, least covered first,
+    /// from the output of `llvm-cov report`
+    fn print_coverage_summary(&self, binary: &Path, profdata_path: &Path) -> Result<()> {
+        let mut report_cmd = Command::new(llvm_tool(cargo_binutils::Tool::Cov)?);
+        report_cmd
+            .arg("report")
+            .arg("--show-functions")
+            .arg(format!("--instr-profile={}", profdata_path.display()))
+            // The fuzz targets live inside the fuzzed crate, but are not part of it
+            .arg(format!(
+                "--ignore-filename-regex={}",
+                regex_escape(&self.fuzz_dir().to_string_lossy())
+            ));
+        if Command::new("rustfilt")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+        {
+            report_cmd.arg("-Xdemangler=rustfilt");
+        }
+        report_cmd.arg(binary).arg(&self.project_dir);
+
+        let output = report_cmd
+            .output()
+            .with_context(|| format!("Failed to run command: {:?}", report_cmd))?;
+        if !output.status.success() {
+            bail!(
+                "Command exited with failure status {}: {:?}\n\n{}",
+                output.status,
+                report_cmd,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
 
+        let mut functions = parse_function_report(&String::from_utf8_lossy(&output.stdout));
+        functions.sort_by(|a, b| {
+            a.cover
+                .total_cmp(&b.cover)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        println!("{:>8} {:>7}  Function", "Lines", "Cover");
+        for function in &functions {
+            println!(
+                "{:>8} {:>6.2}%  {} ({})",
+                function.lines,
+                function.cover,
+                function.name,
+                strip_current_dir_prefix(Path::new(&function.file)).display()
+            );
+        }
         Ok(())
     }
 
@@ -575,7 +1450,7 @@ impl FuzzProject {
     }
 
     fn merge_coverage(profdata_raw_path: &Path, profdata_out_path: &Path) -> Result<()> {
-        let mut merge_cmd = Command::new(cargo_binutils::Tool::Profdata.path()?);
+        let mut merge_cmd = Command::new(llvm_tool(cargo_binutils::Tool::Profdata)?);
         merge_cmd.arg("merge").arg("-sparse");
         merge_cmd.arg(profdata_raw_path);
         merge_cmd.arg("-o").arg(profdata_out_path);
@@ -605,6 +1480,63 @@ impl FuzzProject {
         }
     }
 
+    /// Turns the merged coverage data into a report in `report_dir`
+    fn export_coverage(
+        format: CoverageFormat,
+        binary: &Path,
+        profdata_path: &Path,
+        report_dir: &Path,
+    ) -> Result<()> {
+        let mut cov_cmd = Command::new(llvm_tool(cargo_binutils::Tool::Cov)?);
+        let report_path = match format {
+            CoverageFormat::Lcov => {
+                cov_cmd.arg("export").arg("--format=lcov");
+                report_dir.join("lcov.info")
+            }
+            CoverageFormat::Html => {
+                let html_dir = report_dir.join("html");
+                cov_cmd
+                    .arg("show")
+                    .arg("--format=html")
+                    .arg(format!("--output-dir={}", html_dir.display()));
+                html_dir
+            }
+            CoverageFormat::Text => {
+                let text_dir = report_dir.join("text");
+                cov_cmd
+                    .arg("show")
+                    .arg("--format=text")
+                    .arg(format!("--output-dir={}", text_dir.display()));
+                text_dir
+            }
+        };
+        cov_cmd
+            .arg(format!("--instr-profile={}", profdata_path.display()))
+            .arg(binary);
+
+        eprintln!("Generating {} coverage report...", format);
+        let output = cov_cmd
+            .output()
+            .with_context(|| format!("Failed to run command: {:?}", cov_cmd))?;
+        if !output.status.success() {
+            bail!(
+                "Command exited with failure status {}: {:?}\n\n{}",
+                output.status,
+                cov_cmd,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        // `export` writes the report to stdout, `show` to the output dir
+        if format == CoverageFormat::Lcov {
+            fs::write(&report_path, &output.stdout)
+                .with_context(|| format!("failed to write {}", report_path.display()))?;
+        }
+
+        eprintln!("Coverage report saved in {:?}.", report_path);
+        Ok(())
+    }
+
     fn fuzz_dir(&self) -> &Path {
         &self.fuzz_dir
     }
@@ -627,6 +1559,14 @@ impl FuzzProject {
         Ok((coverage_raw, coverage_data))
     }
 
+    /// Returns the dirs that cargo-libafl creates in the fuzz project, removed by `clean --project`
+    pub fn generated_dirs(&self) -> Vec<PathBuf> {
+        ["target", "corpus", "artifacts", "coverage"]
+            .iter()
+            .map(|dir| self.fuzz_dir().join(dir))
+            .collect()
+    }
+
     fn corpus_for(&self, target: &str) -> Result<PathBuf> {
         let mut p = self.fuzz_dir().to_owned();
         p.push("corpus");
@@ -654,7 +1594,7 @@ impl FuzzProject {
     fn fuzz_targets_dir(&self) -> PathBuf {
         let mut root = self.fuzz_dir().to_owned();
         if root.join(crate::FUZZ_TARGETS_DIR_OLD).exists() {
-            println!(
+            eprintln!(
                 "warning: The `fuzz/fuzzers/` directory has renamed to `fuzz/fuzz_targets/`. \
                  Please rename the directory as such. This will become a hard error in the \
                  future."
@@ -687,6 +1627,149 @@ impl FuzzProject {
         })
     }
 
+    /// Returns the `[package.metadata.cargo-libafl.<target>]` table of the manifest, if any
+    fn target_metadata(&self, target: &str) -> Result<Option<toml::value::Table>> {
+        let manifest = self.manifest()?;
+        Ok(manifest
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("cargo-libafl"))
+            .and_then(|cargo_libafl| cargo_libafl.get(target))
+            .and_then(toml::Value::as_table)
+            .cloned())
+    }
+
+    /// Reads the cargo features of a target from the `features` key of the
+    /// `[package.metadata.cargo-libafl.<target>]` table, as an array or a comma separated string.
+    fn target_features(&self, target: &str) -> Result<Vec<String>> {
+        let features = match self
+            .target_metadata(target)?
+            .and_then(|mut metadata| metadata.remove("features"))
+        {
+            Some(features) => features,
+            None => return Ok(Vec::new()),
+        };
+        let values = match &features {
+            toml::Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        let mut result = Vec::new();
+        for value in values {
+            match value.as_str() {
+                Some(value) => result.extend(
+                    value
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|feature| !feature.is_empty())
+                        .map(String::from),
+                ),
+                None => bail!(
+                    "unsupported value for `features` in [package.metadata.cargo-libafl.{}]: {}",
+                    target,
+                    features
+                ),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Runs the `pre-build` command of the `[package.metadata.cargo-libafl.<target>]` table, if
+    /// any, with `sh -c` in the fuzz crate dir. It is an arbitrary command from the manifest,
+    /// e.g. to generate code or download assets the target needs.
+    fn run_pre_build(&self, build: &BuildOptions, target: &str) -> Result<()> {
+        let command = match self
+            .target_metadata(target)?
+            .and_then(|mut metadata| metadata.remove("pre-build"))
+        {
+            Some(toml::Value::String(command)) => command,
+            Some(value) => bail!(
+                "unsupported value for `pre-build` in [package.metadata.cargo-libafl.{}]: {}",
+                target,
+                value
+            ),
+            None => return Ok(()),
+        };
+        eprintln!("Running the pre-build command of {}: {}", target, command);
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(&command)
+            .current_dir(self.fuzz_dir())
+            .envs(build.env.iter().map(|(key, value)| (key, value)));
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to execute: {:?}", cmd))?;
+        if !status.success() {
+            bail!(
+                "the pre-build command of {} failed with {}: {}",
+                target,
+                status,
+                command
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns `build` with the features of `target` from the manifest added to `--features`.
+    /// They are redundant with `--all-features`, and still apply on top of `--no-default-features`.
+    fn target_build_options(&self, build: &BuildOptions, target: &str) -> Result<BuildOptions> {
+        let mut build = build.clone();
+        if build.all_features {
+            return Ok(build);
+        }
+        let features = self.target_features(target)?;
+        if !features.is_empty() {
+            let mut merged: Vec<String> = build.features.iter().cloned().collect();
+            merged.extend(features);
+            build.features = Some(merged.join(","));
+        }
+        Ok(build)
+    }
+
+    /// Reads the default runtime options of a target from the
+    /// `[package.metadata.cargo-libafl.<target>]` table of the manifest.
+    /// Each key is a long option of the runtime, `true` turns on a flag and an array repeats
+    /// the option. Options that also appear in `explicit_args` are left out, and so are the
+    /// `features`, which are cargo features for the build, see `target_features`, and the
+    /// `pre-build` command, see `run_pre_build`.
+    fn default_runtime_args(&self, target: &str, explicit_args: &[String]) -> Result<Vec<String>> {
+        let defaults = match self.target_metadata(target)? {
+            Some(defaults) => defaults,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut args = Vec::new();
+        for (key, value) in &defaults {
+            if key == "features" || key == "pre-build" {
+                continue;
+            }
+            let flag = format!("--{}", key);
+            if has_runtime_flag(explicit_args, &flag) {
+                continue;
+            }
+
+            let values = match value {
+                toml::Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                match value {
+                    toml::Value::Boolean(true) => args.push(flag.clone()),
+                    toml::Value::Boolean(false) => {}
+                    toml::Value::String(value) => args.push(format!("{}={}", flag, value)),
+                    toml::Value::Integer(_) | toml::Value::Float(_) => {
+                        args.push(format!("{}={}", flag, value))
+                    }
+                    _ => bail!(
+                        "unsupported value for `{}` in [package.metadata.cargo-libafl.{}]: {}",
+                        key,
+                        target,
+                        value
+                    ),
+                }
+            }
+        }
+        Ok(args)
+    }
+
     fn root_project_name(&self) -> Result<String> {
         let filename = self.project_dir.join("Cargo.toml");
         let mut file = fs::File::open(&filename)?;
@@ -792,9 +1875,411 @@ fn find_package() -> Result<PathBuf> {
     bail!("could not find a cargo project")
 }
 
+/// Quotes `arg` for a POSIX shell, unless it only has characters that need no quoting
+fn shell_quote(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if plain {
+        arg.into_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Formats `bytes` like `hexdump -C`, with 16 bytes per line: their offset, hex values and
+/// printable ASCII characters
+fn hexdump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (j, byte) in chunk.iter().enumerate() {
+            if j == 8 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x} ", byte));
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        dump.push_str(&format!("{:08x}  {:<49} |{}|\n", i * 16, hex, ascii));
+    }
+    dump.push_str(&format!("{:08x}\n", bytes.len()));
+    dump
+}
+
+/// Rewrites the common libFuzzer flags like `-max_len=N`, as passed to cargo-fuzz after `--`,
+/// into the runtime flags that do the same. The other libFuzzer flags are dropped with a warning.
+fn translate_libfuzzer_args(args: &[String]) -> Result<Vec<String>> {
+    let mut translated = Vec::with_capacity(args.len());
+    for arg in args {
+        // Unlike the short runtime flags like `-t`, libFuzzer flags have long snake_case names
+        let libfuzzer_flag = arg
+            .strip_prefix('-')
+            .filter(|flag| !flag.starts_with('-'))
+            .map(|flag| flag.split_once('=').unwrap_or((flag, "")))
+            .filter(|(name, _)| {
+                name.len() > 1 && name.bytes().all(|c| c.is_ascii_lowercase() || c == b'_')
+            });
+        let (name, value) = match libfuzzer_flag {
+            Some(flag) => flag,
+            None => {
+                translated.push(arg.clone());
+                continue;
+            }
+        };
+        let flag = match name {
+            "max_len" => "--max-len",
+            "dict" => "--tokens",
+            "runs" => "--max-executions",
+            "max_total_time" => "--max-total-time",
+            "seed" => "--seed",
+            "rss_limit_mb" => "--rss-limit-mb",
+            "timeout" => "--timeout",
+            _ => {
+                eprintln!("warning: ignoring the libFuzzer flag `{}`", arg);
+                continue;
+            }
+        };
+        if value.is_empty() {
+            bail!(
+                "the libFuzzer flag `{}` needs a value, like `-{}=<value>`",
+                arg,
+                name
+            );
+        }
+        // libFuzzer takes the timeout in seconds, the runtime in milliseconds
+        let value = if name == "timeout" {
+            let secs: u64 = value
+                .parse()
+                .with_context(|| format!("invalid timeout in `{}`", arg))?;
+            (secs * 1000).to_string()
+        } else {
+            value.to_string()
+        };
+        translated.push(format!("{}={}", flag, value));
+    }
+    Ok(translated)
+}
+
+/// Picks the error lines out of the output of a failed `cargo build`, or its last lines if
+/// there are none, e.g. when a build script failed
+fn build_errors(stderr: &str) -> Vec<String> {
+    const MAX_LINES: usize = 5;
+    let lines: Vec<&str> = stderr
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let errors: Vec<String> = lines
+        .iter()
+        .filter(|line| line.starts_with("error"))
+        .take(MAX_LINES)
+        .map(|line| line.to_string())
+        .collect();
+    if !errors.is_empty() {
+        return errors;
+    }
+    lines[lines.len().saturating_sub(MAX_LINES)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Whether `flag` was passed in `args`, either on its own or as `flag=value`
+fn has_runtime_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| {
+        arg == flag
+            || arg
+                .strip_prefix(flag)
+                .is_some_and(|rest| rest.starts_with('='))
+    })
+}
+
+/// Counts the entries of `dir`, skipping the hidden files LibAFL keeps next to them,
+/// or returns `None` if it does not exist
+fn count_entries(dir: &Path) -> Option<usize> {
+    let entries = fs::read_dir(dir).ok()?;
+    Some(
+        entries
+            .flatten()
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .count(),
+    )
+}
+
+/// Builds the runtime for `triple` and the extra `rustflags` unless it is cached already,
+/// i.e. for other targets than the one cargo-libafl was installed for, or with `--rustflags`
+fn ensure_runtime(triple: &str, rustflags: &[String]) -> Result<()> {
+    let archive = common::archive_file_path(triple, rustflags);
+    // A custom runtime is never built here, it is up to the user to provide it
+    if archive.is_file() || env::var_os("CUSTOM_LIBAFL_RUNTIME").is_some() {
+        return Ok(());
+    }
+
+    eprintln!("Building the fuzzer runtime for {}", triple);
+    let build_dir = tempfile::tempdir().context("failed to create temp dir")?;
+    fs::write(build_dir.path().join("Cargo.toml"), RUNTIME_MANIFEST)?;
+    for (file, source) in RUNTIME_SOURCES {
+        fs::write(build_dir.path().join(file), source)?;
+    }
+    let target_dir = build_dir.path().join("target");
+    let mut cmd = common::runtime_build_command(build_dir.path(), &target_dir, triple, rustflags);
+    let status = cmd
+        .status()
+        .with_context(|| format!("failed to execute: {:?}", cmd))?;
+    if !status.success() {
+        bail!(
+            "failed to build the fuzzer runtime for {}: {:?}",
+            triple,
+            cmd
+        );
+    }
+    fs::copy(common::built_archive(&target_dir, triple), &archive)
+        .with_context(|| format!("failed to copy the fuzzer runtime to {}", archive.display()))?;
+    Ok(())
+}
+
+/// Rejects sanitizer combinations that cannot work together or on the target triple
+fn check_sanitizers(build: &BuildOptions) -> Result<()> {
+    let sanitizers = &build.sanitizer;
+    if sanitizers.contains(&Sanitizer::None) && sanitizers.len() > 1 {
+        bail!("the `none` sanitizer cannot be combined with other sanitizers");
+    }
+
+    // Each of these brings its own runtime and shadow memory layout
+    let exclusive = [
+        Sanitizer::Address,
+        Sanitizer::HwAddress,
+        Sanitizer::Memory,
+        Sanitizer::Thread,
+    ];
+    let selected: Vec<String> = exclusive
+        .iter()
+        .filter(|sanitizer| sanitizers.contains(sanitizer))
+        .map(ToString::to_string)
+        .collect();
+    if selected.len() > 1 {
+        bail!(
+            "the {} sanitizers cannot be combined",
+            selected.join(" and ")
+        );
+    }
+    if sanitizers.contains(&Sanitizer::Leak)
+        && (sanitizers.contains(&Sanitizer::Memory) || sanitizers.contains(&Sanitizer::Thread))
+    {
+        bail!("the leak sanitizer cannot be combined with the memory or thread sanitizers");
+    }
+
+    if sanitizers.contains(&Sanitizer::HwAddress) && !build.triple.starts_with("aarch64-") {
+        bail!(
+            "the hwaddress sanitizer needs pointer tagging, which is only supported on \
+             aarch64 targets, not on {}",
+            build.triple
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the path of an LLVM tool shipped with the `llvm-tools-preview` component
+fn llvm_tool(tool: cargo_binutils::Tool) -> Result<PathBuf> {
+    let path = tool.path()?;
+    if !path.exists() {
+        bail!(
+            "Could not find `{}` at {}.\n\
+             \n\
+             Do you have LLVM coverage tools installed?\n\
+             Try `rustup component add llvm-tools-preview`.",
+            tool.exe(),
+            path.display()
+        );
+    }
+    Ok(path)
+}
+
+/// A function in the output of `llvm-cov report --show-functions`
+struct FunctionCoverage {
+    name: String,
+    file: String,
+    lines: u64,
+    /// The percentage of covered lines
+    cover: f64,
+}
+
+/// Parses the per-file function tables of `llvm-cov report --show-functions`.
+/// Each row is a name followed by region, line and maybe branch counts, e.g.
+/// `my_crate::parse  10  2  80.00%  20  3  85.00%`.
+fn parse_function_report(report: &str) -> Vec<FunctionCoverage> {
+    let mut functions = Vec::new();
+    let mut file = String::new();
+    for line in report.lines() {
+        if let Some(name) = line
+            .strip_prefix("File '")
+            .and_then(|rest| rest.strip_suffix("':"))
+        {
+            file = name.to_string();
+            continue;
+        }
+        if file.is_empty() || line.starts_with("Name") || line.starts_with("TOTAL") {
+            continue;
+        }
+
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        // The counts are the trailing numeric columns, the name may contain spaces
+        let counts = columns
+            .iter()
+            .rev()
+            .take_while(|column| {
+                column.trim_end_matches('%').parse::<f64>().is_ok() || **column == "-"
+            })
+            .count();
+        if counts < 6 || counts == columns.len() {
+            continue;
+        }
+        let counts = &columns[columns.len() - counts..];
+        let (Ok(lines), Ok(cover)) = (
+            counts[3].parse::<u64>(),
+            counts[5].trim_end_matches('%').parse::<f64>(),
+        ) else {
+            continue;
+        };
+        functions.push(FunctionCoverage {
+            name: columns[..columns.len() - counts.len()].join(" "),
+            file: file.clone(),
+            lines,
+            cover,
+        });
+    }
+    functions
+}
+
+/// Escapes the regex metacharacters in `text`, for the regex arguments of the LLVM tools
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 fn strip_current_dir_prefix(path: &Path) -> &Path {
     env::current_dir()
         .ok()
         .and_then(|curdir| path.strip_prefix(curdir).ok())
         .unwrap_or(path)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn translate_libfuzzer_flags() {
+        assert_eq!(
+            translate_libfuzzer_args(&args(&["-max_len=64", "-timeout=2", "-t", "--seed=1"]))
+                .unwrap(),
+            args(&["--max-len=64", "--timeout=2000", "-t", "--seed=1"])
+        );
+        assert_eq!(
+            translate_libfuzzer_args(&args(&["-use_value_profile=1"])).unwrap(),
+            Vec::<String>::new()
+        );
+        assert!(translate_libfuzzer_args(&args(&["-runs"])).is_err());
+        assert!(translate_libfuzzer_args(&args(&["-timeout=1s"])).is_err());
+    }
+
+    #[test]
+    fn find_runtime_flag() {
+        let passed = args(&["--timeout=10", "--seed", "3"]);
+        assert!(has_runtime_flag(&passed, "--timeout"));
+        assert!(has_runtime_flag(&passed, "--seed"));
+        assert!(!has_runtime_flag(&passed, "--time"));
+        assert!(!has_runtime_flag(&passed, "--max-len"));
+    }
+
+    #[test]
+    fn quote_for_shell() {
+        assert_eq!(shell_quote(OsStr::new("fuzz/corpus-1")), "fuzz/corpus-1");
+        assert_eq!(shell_quote(OsStr::new("")), "''");
+        assert_eq!(shell_quote(OsStr::new("a b")), "'a b'");
+        assert_eq!(shell_quote(OsStr::new("it's")), "'it'\\''s'");
+    }
+
+    #[test]
+    fn hexdump_lines() {
+        assert_eq!(hexdump(b""), "00000000\n");
+        let dump = hexdump(b"0123456789abcdef\n");
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|",
+                "00000010  0a                                                |.|",
+                "00000011",
+            ]
+        );
+    }
+
+    #[test]
+    fn pick_build_errors() {
+        let stderr =
+            "   Compiling fuzzed\nerror[E0425]: cannot find value `x`\n\nerror: aborting\n";
+        assert_eq!(
+            build_errors(stderr),
+            ["error[E0425]: cannot find value `x`", "error: aborting"]
+        );
+        let stderr = "1\n2\n3\n4\n5\n6\n";
+        assert_eq!(build_errors(stderr), ["2", "3", "4", "5", "6"]);
+    }
+
+    #[test]
+    fn parse_llvm_cov_functions() {
+        let report = "\
+File '/src/lib.rs':
+Name                Regions    Miss   Cover     Lines    Miss   Cover
+---------------------------------------------------------------------
+fuzzed::parse            10       2  80.00%        20       3  85.00%
+<fuzzed::A as Trait>::f   4       4   0.00%         8       8   0.00%
+---------------------------------------------------------------------
+TOTAL                    14       6  57.14%        28      11  60.71%
+";
+        let functions = parse_function_report(report);
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].name, "fuzzed::parse");
+        assert_eq!(functions[0].file, "/src/lib.rs");
+        assert_eq!(functions[0].lines, 20);
+        assert_eq!(functions[0].cover, 85.0);
+        assert_eq!(functions[1].name, "<fuzzed::A as Trait>::f");
+        assert_eq!(functions[1].cover, 0.0);
+    }
+
+    #[test]
+    fn escape_regex() {
+        assert_eq!(regex_escape("a::b"), "a::b");
+        assert_eq!(regex_escape("<T as Tr>::f(x)"), "<T as Tr>::f\\(x\\)");
+        assert_eq!(regex_escape("a.b*"), "a\\.b\\*");
+    }
+
+    #[test]
+    fn embed_all_runtime_sources() {
+        let files: Vec<&str> = RUNTIME_SOURCES.iter().map(|(file, _)| *file).collect();
+        assert_eq!(files, common::RUNTIME_SOURCES);
+        assert!(RUNTIME_SOURCES.iter().all(|(_, source)| !source.is_empty()));
+    }
+}
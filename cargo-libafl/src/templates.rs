@@ -11,8 +11,15 @@ edition = "2018"
 [package.metadata]
 cargo-fuzz = true
 
+# Default runtime options of a fuzz target, the command line takes precedence
+# [package.metadata.cargo-libafl.<target>]
+# timeout = 2000
+# cores = "0-3"
+# features = ["some-feature"]
+
 [dependencies]
-cargo-libafl-helper = "0"
+# The helper version has to match the runtime of cargo-libafl
+cargo-libafl-helper = "0.2"
 
 [dependencies.{0}]
 path = ".."
@@ -27,16 +34,16 @@ members = ["."]
 }
 
 macro_rules! toml_bin_template {
-    ($name: expr) => {
+    ($name: expr, $dir: expr) => {
         format_args!(
             r#"
 [[bin]]
 name = "{0}"
-path = "fuzz_targets/{0}.rs"
+path = "{1}/{0}.rs"
 test = false
 doc = false
 "#,
-            $name
+            $name, $dir
         )
     };
 }
@@ -66,3 +73,26 @@ fuzz_target!(|data: &[u8]| {{
         )
     };
 }
+
+macro_rules! arbitrary_target_template {
+    ($ty: expr) => {
+        format_args!(
+            r##"#![no_main]
+use cargo_libafl_helper::{{
+    arbitrary::{{self, Arbitrary}},
+    fuzz_target,
+}};
+
+#[derive(Arbitrary, Debug)]
+pub struct {0} {{
+    // fields of the structured input go here
+}}
+
+fuzz_target!(|input: {0}| {{
+    // fuzzed code goes here
+}});
+"##,
+            $ty
+        )
+    };
+}
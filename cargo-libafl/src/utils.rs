@@ -1,4 +1,115 @@
+use std::{env, ffi::OsString, fs, path::Path, process::Command, sync::OnceLock};
+
+/// Set to the name of the fuzzed package when cargo-libafl is the `RUSTC_WRAPPER` of
+/// `--no-cfg-fuzzing-deps`
+pub const CFG_FUZZING_WRAPPER_ENV: &str = "CARGO_LIBAFL_CFG_FUZZING_WRAPPER";
+
+/// Runs rustc as cargo passed it to the wrapper and exits with its exit code.
+/// `--cfg fuzzing` is added for the fuzz crate, which cargo builds as the primary package,
+/// and for the fuzzed package, but not for their dependencies or build scripts.
+pub fn run_cfg_fuzzing_wrapper() -> ! {
+    let mut args = env::args_os().skip(1);
+    let rustc = args
+        .next()
+        .expect("cargo passes the path of rustc to the wrapper");
+    let args: Vec<OsString> = args.collect();
+    let is_build_script = args.iter().any(|arg| arg == "build_script_build");
+    let is_fuzzed = env::var_os("CARGO_PRIMARY_PACKAGE").is_some()
+        || env::var_os("CARGO_PKG_NAME") == env::var_os(CFG_FUZZING_WRAPPER_ENV);
+    let mut cmd = Command::new(&rustc);
+    cmd.args(&args);
+    if is_fuzzed && !is_build_script {
+        cmd.arg("--cfg").arg("fuzzing");
+    }
+    let status = cmd.status();
+    match status {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!("failed to execute {:?}: {}", rustc, e);
+            std::process::exit(1);
+        }
+    }
+}
+
 /// The default target to pass to cargo, to workaround issue #11.
+/// It is the host of `rustc`, which is not the `TARGET` cargo-libafl was built for when that
+/// runs emulated, e.g. an x86_64 build under Rosetta next to an aarch64 toolchain.
+/// `TARGET` is only used if `rustc` cannot be queried.
 pub fn default_target() -> &'static str {
-    option_env!("TARGET").unwrap_or("x86_64-unknown-linux-gnu")
+    static HOST: OnceLock<String> = OnceLock::new();
+    HOST.get_or_init(|| {
+        rustc_version::version_meta()
+            .map_or_else(|_| String::from(env!("TARGET")), |meta| meta.host)
+    })
+}
+
+/// The total size in bytes of the files in `path` and its subdirs
+pub fn dir_size(path: &Path) -> u64 {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Quotes `s` as a JSON string
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Formats a size in bytes for humans, e.g. `1.5 MiB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quote_json() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("a \"b\"\\"), "\"a \\\"b\\\"\\\\\"");
+        assert_eq!(json_string("\n\t\u{1}"), "\"\\n\\t\\u0001\"");
+    }
+
+    #[test]
+    fn human_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+        assert_eq!(format_size(u64::MAX), "16777216.0 TiB");
+    }
 }
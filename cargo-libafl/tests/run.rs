@@ -0,0 +1,75 @@
+//! Runs the fuzzer end to end on a fresh fuzz project, which is built first, so this takes a while.
+//! Ignored by default, run it with `cargo test -- --ignored`.
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long `run --max-total-time 1` may take to return, including the startup of the fuzzer
+const RUN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The `cargo libafl` command, run in `dir`
+fn cargo_libafl(dir: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_cargo-libafl"));
+    cmd.current_dir(dir).arg("libafl");
+    cmd
+}
+
+#[test]
+#[ignore = "builds the runtime, which needs network access and takes minutes"]
+fn max_total_time_returns() {
+    let dir = tempfile::tempdir().unwrap();
+    let status = Command::new("cargo")
+        .current_dir(dir.path())
+        .args(["new", "--lib", "--vcs", "none", "fuzzed"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let project = dir.path().join("fuzzed");
+    let status = cargo_libafl(&project).arg("init").status().unwrap();
+    assert!(status.success());
+
+    // Fuzz with the helper of this checkout, not the published one
+    let manifest = project.join("fuzz").join("Cargo.toml");
+    let helper = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("cargo-libafl-helper");
+    let toml = fs::read_to_string(&manifest).unwrap().replace(
+        "cargo-libafl-helper = \"0.2\"",
+        &format!("cargo-libafl-helper = {{ path = {:?} }}", helper),
+    );
+    fs::write(&manifest, toml).unwrap();
+
+    // Built beforehand, so that the build time does not count towards the timeout
+    let status = cargo_libafl(&project)
+        .args(["build", "--sanitizer", "none", "fuzz_target_1"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let mut fuzzer = cargo_libafl(&project)
+        .args(["run", "--sanitizer", "none", "--max-total-time", "1"])
+        .arg("fuzz_target_1")
+        .stdin(Stdio::null())
+        .spawn()
+        .unwrap();
+    let deadline = Instant::now() + RUN_TIMEOUT;
+    loop {
+        if let Some(status) = fuzzer.try_wait().unwrap() {
+            assert!(status.success(), "the fuzzer failed with {}", status);
+            return;
+        }
+        if Instant::now() >= deadline {
+            let _ = fuzzer.kill();
+            panic!(
+                "the fuzzer did not return within {}s with --max-total-time 1",
+                RUN_TIMEOUT.as_secs()
+            );
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}